@@ -0,0 +1,89 @@
+"""Per-call latency of small file operations.
+
+Compares the typed API path of `Xvc.file()` methods with `Xvc.call()`, which builds a command
+line and parses it for each call. Both use the same `Xvc` object, so the repository root is
+loaded and logging is set up once for both, and the difference is the command line handling.
+
+Run from the repository root after `maturin develop`:
+
+    python benches/bench_file_calls.py [--calls 200]
+"""
+
+import argparse
+import os
+import subprocess
+import tempfile
+import time
+
+from xvc import Xvc
+
+
+def timed(label, calls, fn):
+    start = time.perf_counter()
+    for i in range(calls):
+        fn(i)
+    elapsed = time.perf_counter() - start
+    print(f"{label:<32} {calls:>6} calls {elapsed * 1000 / calls:>10.3f} ms/call")
+    return elapsed
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--calls", type=int, default=200)
+    args = parser.parse_args()
+    calls = args.calls
+
+    with tempfile.TemporaryDirectory() as repo:
+        os.chdir(repo)
+        subprocess.run(["git", "init", "--quiet"], check=True)
+        for i in range(calls):
+            with open(f"file-{i:05}.txt", "w") as f:
+                f.write(f"content {i}\n")
+
+        xvc = Xvc(skip_git=True)
+        xvc.init()
+
+        half = calls // 2
+        results = [
+            (
+                "hash",
+                timed(
+                    "file().hash (API)",
+                    calls,
+                    lambda i: xvc.file().hash(f"file-{i:05}.txt"),
+                ),
+                timed(
+                    "call file hash (CLI)",
+                    calls,
+                    lambda i: xvc.call(["file", "hash"], f"file-{i:05}.txt"),
+                ),
+            ),
+            (
+                "track",
+                timed(
+                    "file().track (API)",
+                    half,
+                    lambda i: xvc.file().track(f"file-{i:05}.txt", no_commit=True),
+                ),
+                timed(
+                    "call file track (CLI)",
+                    half,
+                    lambda i: xvc.call(
+                        ["file", "track"], f"file-{half + i:05}.txt", no_commit=True
+                    ),
+                ),
+            ),
+            (
+                "list",
+                timed("file().list (API)", 20, lambda i: xvc.file().list()),
+                timed("call file list (CLI)", 20, lambda i: xvc.call(["file", "list"])),
+            ),
+        ]
+
+        print()
+        for name, api, cli in results:
+            print(f"{name:<8} API/CLI time ratio {api / cli:.2f}")
+
+
+if __name__ == "__main__":
+    main()
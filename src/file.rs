@@ -1,9 +1,73 @@
+pub mod data;
+pub mod dataset;
+pub mod duplicates;
+pub mod hash;
+pub mod history;
+pub mod labels;
+pub mod list;
+pub mod policy;
+pub mod put;
+pub mod remove;
+pub mod share;
+pub mod status;
+pub mod store;
+pub mod transfer;
+pub mod untrack;
+pub mod url;
+pub mod verify;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use pyo3::exceptions::{PyFileExistsError, PyFileNotFoundError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyTuple};
+use pyo3::IntoPyObjectExt;
+use xvc_rust::core::XvcRoot;
+use xvc_rust::file::{
+    bring::BringCLI,
+    carry_in::CarryInCLI,
+    copy::CopyCLI,
+    hash::HashCLI,
+    list::{ListCLI, ListSortCriteria},
+    mv::MoveCLI,
+    recheck::RecheckCLI,
+    remove::RemoveCLI,
+    send::SendCLI,
+    share::ShareCLI,
+    track::TrackCLI,
+    untrack::UntrackCLI,
+    XvcFileCLI, XvcFileSubCommand,
+};
 use xvc_rust::watch;
 
-use crate::{update_cli_flag, update_cli_opt};
-use crate::{update_targets, Xvc};
+use dataset::Dataset;
+use duplicates::DuplicateReport;
+use hash::{Digest, HashResult};
+use history::{FileDiff, FileVersion};
+use list::{FileEntry, FileList};
+use policy::{Policy, RecheckPolicy};
+use share::SharedUrl;
+use status::FileStatus;
+use store::{Records, StoragePaths, TrackedFile};
+use transfer::{BatchErrors, BringFilter, Progress};
+use url::{TrackedUrl, UrlSource};
+use verify::VerifyReport;
+
+use crate::options::{
+    merge_options, BringOptions, CarryInOptions, CopyOptions, HashOptions, ListFormat, ListOptions,
+    MoveOptions, RecheckOptions, RemoveOptions, SendOptions, ShareOptions, TrackOptions,
+    UntrackOptions,
+};
+use crate::output::{
+    dispatch_file, output_errors, raise_on_error, run_with_output, CommandContext,
+};
+use crate::targets::{TargetChunks, COMMAND_STRING_TARGETS};
+use crate::{
+    check_opts, get_flag, get_parsed, get_required, get_string, get_targets, get_values,
+    parse_value, Xvc,
+};
 
 #[pyclass]
 #[derive(Clone, Debug)]
@@ -24,301 +88,1485 @@ impl XvcFile {
         Ok(cli_opts)
     }
 
-    fn run(&self, args: Vec<String>) -> PyResult<String> {
-        watch!(args);
-        self.xvc_opts.run(args)
+    /// Show the help text of a subcommand
+    fn help(&self, subcommand: &str) -> PyResult<String> {
+        let mut cli_opts = self.cli()?;
+        cli_opts.push(subcommand.to_string());
+        cli_opts.push("--help".to_string());
+        self.xvc_opts.run(cli_opts)
+    }
+
+    /// Help text of the subcommand, for methods that return structured results
+    fn help_object(&self, py: Python, subcommand: &str) -> PyResult<PyObject> {
+        self.help(subcommand)?.into_py_any(py)
+    }
+
+    /// Entries of `file list` for `targets`. Workspace files are hashed only with
+    /// `actual_digests`.
+    fn entries(
+        &self,
+        targets: Vec<String>,
+        sort: Option<ListSortCriteria>,
+        include_git_files: bool,
+        show_dirs: bool,
+        actual_digests: bool,
+    ) -> PyResult<Vec<FileEntry>> {
+        let command = command_string("list", &targets);
+        let format = if actual_digests {
+            list::ENTRY_FORMAT
+        } else {
+            list::LOOKUP_FORMAT
+        };
+        let list_cli = ListCLI {
+            format: Some(parse_value(format, "format")?),
+            sort,
+            no_summary: true,
+            include_git_files,
+            show_dirs,
+            targets: optional_targets(targets),
+        };
+        let output = self.run_without_git(XvcFileSubCommand::List(list_cli), command)?;
+        raise_on_error(&output)?;
+        Ok(list::parse_entries(&output, actual_digests))
+    }
+
+    /// Rules of the recheck method policy
+    fn policy_rules(&self) -> PyResult<Vec<policy::PolicyRule>> {
+        Ok(Policy::load(&store::xvc_root(&self.xvc_opts)?)?.rules)
+    }
+
+    /// Tracked files in `targets`, or in the whole workspace if no targets are given
+    fn tracked_files(&self, targets: Vec<String>) -> PyResult<(XvcRoot, Vec<TrackedFile>)> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let paths: HashSet<String> = self
+            .entries(targets, None, false, false, false)?
+            .into_iter()
+            .filter(|e| e.recorded_digest.is_some())
+            .map(|e| e.path)
+            .collect();
+        let files = Records::load(&xvc_root, None)?.tracked_files(&xvc_root, &paths)?;
+        Ok((xvc_root, files))
+    }
+
+    /// Run the subcommand with the API, without building a command line
+    fn run(&self, subcommand: XvcFileSubCommand, command_string: String) -> PyResult<String> {
+        self.run_in(subcommand, self.xvc_opts.context(command_string))
+    }
+
+    /// Run the subcommand without Git automation, for commands that are run for each file and
+    /// committed once with [XvcFile::commit]
+    fn run_without_git(
+        &self,
+        subcommand: XvcFileSubCommand,
+        command_string: String,
+    ) -> PyResult<String> {
+        let mut context = self.xvc_opts.context(command_string);
+        context.skip_git = true;
+        self.run_in(subcommand, context)
+    }
+
+    /// Run Git automation for the changes of a command run with [XvcFile::run_without_git]
+    fn commit(&self, command_string: String) -> PyResult<()> {
+        let mut context = self.xvc_opts.context(command_string);
+        context.from_ref = None;
+        let output = run_with_output(&self.xvc_opts.xvc_root_opt, &context, |_| Ok(()))?;
+        raise_on_error(&output.output)
+    }
+
+    /// Call `run_chunk` with each chunk of `targets` and its command string, or once with `None`
+    /// if there are no targets, and commit the changes once. `run_chunk` runs the subcommand
+    /// with [XvcFile::run_without_git].
+    fn run_chunks(
+        &self,
+        subcommand: &str,
+        targets: &Bound<PyTuple>,
+        mut run_chunk: impl FnMut(Option<Vec<String>>, String) -> PyResult<String>,
+    ) -> PyResult<String> {
+        let mut chunks = TargetChunks::new(targets);
+        let mut output = String::new();
+        let result = loop {
+            let chunk = match chunks.next_chunk() {
+                Ok(Some(chunk)) => Some(chunk),
+                Ok(None) if chunks.count() == 0 => None,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            };
+            let last = chunk.is_none();
+            let command = command_string(subcommand, chunk.as_deref().unwrap_or_default());
+            match run_chunk(chunk, command) {
+                Ok(chunk_output) => output.push_str(&chunk_output),
+                Err(e) => break Err(e),
+            }
+            if last {
+                break Ok(());
+            }
+        };
+        // The chunks that ran are committed even if a later one fails
+        self.commit(listed_command_string(
+            subcommand,
+            chunks.listed(),
+            chunks.count(),
+        ))?;
+        result.map(|_| output)
+    }
+
+    fn run_in(&self, subcommand: XvcFileSubCommand, context: CommandContext) -> PyResult<String> {
+        watch!(subcommand);
+        let config_params = &self.xvc_opts.xvc_config_params;
+        let xvc_file_cli = XvcFileCLI {
+            verbosity: self.xvc_opts.verbosity.unwrap_or_default(),
+            quiet: self.xvc_opts.quiet.unwrap_or_default(),
+            workdir: self
+                .xvc_opts
+                .workdir
+                .clone()
+                .unwrap_or_else(|| ".".to_string()),
+            config: None,
+            no_system_config: !config_params.include_system_config,
+            no_user_config: !config_params.include_user_config,
+            no_project_config: false,
+            no_local_config: false,
+            no_env_config: !config_params.include_environment_config,
+            subcommand,
+        };
+        let out = dispatch_file(&self.xvc_opts.xvc_root_opt, &context, xvc_file_cli)?;
+        Ok(out.output)
+    }
+}
+
+/// The command line equivalent of a subcommand, used in Git commit messages
+fn command_string(subcommand: &str, targets: &[String]) -> String {
+    listed_command_string(
+        subcommand,
+        &targets[..targets.len().min(COMMAND_STRING_TARGETS)],
+        targets.len(),
+    )
+}
+
+/// The command string of a subcommand with `count` targets, of which `listed` are shown
+fn listed_command_string(subcommand: &str, listed: &[String], count: usize) -> String {
+    let mut command = format!("xvc file {}", subcommand);
+    for target in listed {
+        command.push(' ');
+        command.push_str(target);
+    }
+    if count > listed.len() {
+        command.push_str(&format!(" ... ({} more targets)", count - listed.len()));
+    }
+    command
+}
+
+/// No targets mean all files for most of the commands
+fn optional_targets(targets: Vec<String>) -> Option<Vec<String>> {
+    if targets.is_empty() {
+        None
+    } else {
+        Some(targets)
     }
 }
 
 #[pymethods]
 impl XvcFile {
-    #[pyo3( signature = (*targets, **opts))]
-    fn track(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("track".to_string());
-
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["recheck-method", "recheck_method"],
-            "--recheck-method",
-        )?;
-        update_cli_flag(
-            opts,
-            &mut cli_opts,
-            &["no-commit", "no_commit"],
-            "--no-commit",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["text-or-binary", "text_or_binary"],
-            "--text-or-binary",
-        )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(
-            opts,
-            &mut cli_opts,
-            &["no-parallel", "no_parallel"],
-            "--no-parallel",
-        )?;
-        update_cli_flag(
+    /// Track the files in `targets`. Without `recheck_method`, files matching the rules of
+    /// [XvcFile::policy] are checked out with their methods.
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn track(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<TrackOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["include-git-files", "include_git_files"],
-            "--include-git-files",
+            &[
+                &["help"],
+                &["recheck-method", "recheck_method"],
+                &["no-commit", "no_commit"],
+                &["text-or-binary", "text_or_binary"],
+                &["force"],
+                &["no-parallel", "no_parallel"],
+                &["include-git-files", "include_git_files"],
+            ],
         )?;
+        if get_flag(opts, &["help"])? {
+            return self.help("track");
+        }
 
-        update_targets(targets, cli_opts.as_mut())?;
+        let recheck_method = get_parsed(opts, &["recheck-method", "recheck_method"])?;
+        let use_policy = recheck_method.is_none() && !self.policy_rules()?.is_empty();
+        let no_commit = get_flag(opts, &["no-commit", "no_commit"])?;
+        let text_or_binary = get_parsed(opts, &["text-or-binary", "text_or_binary"])?;
+        let force = get_flag(opts, &["force"])?;
+        let no_parallel = get_flag(opts, &["no-parallel", "no_parallel"])?;
+        let include_git_files = get_flag(opts, &["include-git-files", "include_git_files"])?;
         watch!(self);
 
-        self.run(cli_opts)
+        self.run_chunks("track", targets, |targets, command| {
+            let policy_targets = if use_policy { targets.clone() } else { None };
+            let track_cli = TrackCLI {
+                recheck_method,
+                no_commit,
+                text_or_binary,
+                force,
+                no_parallel,
+                include_git_files,
+                targets,
+            };
+            let mut output = self.run_without_git(XvcFileSubCommand::Track(track_cli), command)?;
+            if use_policy {
+                output.push_str(&policy::apply(
+                    py,
+                    self,
+                    policy_targets.unwrap_or_default(),
+                )?);
+            }
+            Ok(output)
+        })
     }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn hash(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("hash".to_string());
-
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        update_cli_opt(opts, &mut cli_opts, &["algorithm"], "--algorithm")?;
-        update_cli_opt(
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn hash(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<HashOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["text-or-binary", "text_or_binary"],
-            "--text-or-binary",
+            &[
+                &["help"],
+                &["algorithm"],
+                &["text-or-binary", "text_or_binary"],
+            ],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
-    }
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "hash");
+        }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn carry_in(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("carry-in".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        let algorithm = match get_parsed(opts, &["algorithm"])? {
+            Some(algorithm) => algorithm,
+            None => hash::configured_algorithm(&store::xvc_root(&self.xvc_opts)?),
+        };
+        let text_or_binary =
+            get_parsed(opts, &["text-or-binary", "text_or_binary"])?.unwrap_or_default();
+        let mut chunks = TargetChunks::new(targets);
+        let mut digests = Vec::new();
+        while let Some(chunk) = chunks.next_chunk()? {
+            let command = command_string("hash", &chunk);
+            let hash_cli = HashCLI {
+                algorithm: Some(algorithm),
+                text_or_binary,
+                targets: chunk.into_iter().map(PathBuf::from).collect(),
+            };
+            let output = self.run_without_git(XvcFileSubCommand::Hash(hash_cli), command)?;
+            raise_on_error(&output)?;
+            digests.extend(hash::parse_digests(&output, &algorithm.to_string()).digests);
+        }
+        HashResult { digests }.into_py_any(py)
+    }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["text-or-binary", "text_or_binary"],
-            "--text-or-binary",
-        )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(
+    /// Calculate the digest of `bytes`, a buffer or a binary file-like object with the rules used
+    /// for workspace files
+    #[pyo3(signature = (data, options=None, **opts))]
+    fn hash_data(
+        &self,
+        py: Python,
+        data: &Bound<PyAny>,
+        options: Option<&Bound<HashOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<Digest> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["no-parallel", "no_parallel"],
-            "--no-parallel",
+            &[&["algorithm"], &["text-or-binary", "text_or_binary"]],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
-    }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn recheck(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("recheck".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        hash::hash_data(
+            py,
+            &store::xvc_root(&self.xvc_opts)?,
+            data,
+            get_parsed(opts, &["algorithm"])?,
+            get_parsed(opts, &["text-or-binary", "text_or_binary"])?,
+        )
+    }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["recheck-method", "recheck_method"],
-            "--recheck-method",
-        )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn carry_in(
+        &self,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<CarryInOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["no-parallel", "no_parallel"],
-            "--no-parallel",
+            &[
+                &["help"],
+                &["text-or-binary", "text_or_binary"],
+                &["force"],
+                &["no-parallel", "no_parallel"],
+            ],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
-    }
+        if get_flag(opts, &["help"])? {
+            return self.help("carry-in");
+        }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn list(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("list".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        let text_or_binary = get_parsed(opts, &["text-or-binary", "text_or_binary"])?;
+        let force = get_flag(opts, &["force"])?;
+        let no_parallel = get_flag(opts, &["no-parallel", "no_parallel"])?;
+        self.run_chunks("carry-in", targets, |targets, command| {
+            let carry_in_cli = CarryInCLI {
+                text_or_binary,
+                force,
+                no_parallel,
+                targets,
+            };
+            self.run_without_git(XvcFileSubCommand::CarryIn(carry_in_cli), command)
+        })
+    }
 
-        update_cli_opt(opts, &mut cli_opts, &["format"], "--format")?;
-        update_cli_opt(opts, &mut cli_opts, &["sort"], "--sort")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-summary"], "--no-summary")?;
-        update_cli_flag(
+    /// Check out the tracked files in `targets` from the cache. Without `recheck_method`, files
+    /// matching the rules of [XvcFile::policy] are checked out with their methods.
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn recheck(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<RecheckOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["include-git-files", "include_git_files"],
-            "--include-git-files",
+            &[
+                &["help"],
+                &["recheck-method", "recheck_method"],
+                &["force"],
+                &["no-parallel", "no_parallel"],
+            ],
         )?;
-        update_cli_flag(
+        if get_flag(opts, &["help"])? {
+            return self.help("recheck");
+        }
+
+        let recheck_method = get_parsed(opts, &["recheck-method", "recheck_method"])?;
+        let use_policy = recheck_method.is_none() && !self.policy_rules()?.is_empty();
+        let force = get_flag(opts, &["force"])?;
+        let no_parallel = get_flag(opts, &["no-parallel", "no_parallel"])?;
+        self.run_chunks("recheck", targets, |targets, command| {
+            let policy_targets = if use_policy { targets.clone() } else { None };
+            let recheck_cli = RecheckCLI {
+                recheck_method,
+                force,
+                no_parallel,
+                targets,
+            };
+            let mut output =
+                self.run_without_git(XvcFileSubCommand::Recheck(recheck_cli), command)?;
+            if use_policy {
+                output.push_str(&policy::apply(
+                    py,
+                    self,
+                    policy_targets.unwrap_or_default(),
+                )?);
+            }
+            Ok(output)
+        })
+    }
+
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn list(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<ListOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
             &[
-                "show-dirs",
-                "show-directories",
-                "show_directories",
-                "show_dirs",
-                "d",
+                &["help"],
+                &["format"],
+                &["sort"],
+                &["no-summary", "no_summary"],
+                &["include-git-files", "include_git_files"],
+                &[
+                    "show-dirs",
+                    "show-directories",
+                    "show_directories",
+                    "show_dirs",
+                    "d",
+                ],
+                &["labels"],
             ],
-            "--show-directories",
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
-    }
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "list");
+        }
+        let label_filter = match opts.map(|o| o.get_item("labels")).transpose()?.flatten() {
+            Some(labels) => labels::label_values(Some(labels.downcast::<PyDict>()?))?,
+            None => BTreeMap::new(),
+        };
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn send(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("send".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        let template = match get_string(opts, &["format"])? {
+            Some(template) => template,
+            None => ListFormat::Default.cli_value().to_string(),
+        };
+        let show_summary = !get_flag(opts, &["no-summary", "no_summary"])?;
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["remote", "to", "storage"],
-            "--storage",
+        let entries = self.entries(
+            get_targets(targets)?,
+            get_parsed(opts, &["sort"])?,
+            get_flag(opts, &["include-git-files", "include_git_files"])?,
+            get_flag(
+                opts,
+                &[
+                    "show-dirs",
+                    "show-directories",
+                    "show_directories",
+                    "show_dirs",
+                    "d",
+                ],
+            )?,
+            true,
         )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
+        let entries = labels::apply(&store::xvc_root(&self.xvc_opts)?, entries, &label_filter)?;
+        FileList::new(entries, template, show_summary).into_py_any(py)
     }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn bring(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("bring".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    /// Status of tracked and untracked files in `targets`, or in the whole workspace when no
+    /// targets are given. Cache files are also checked in `storage` when it's given.
+    #[pyo3(signature = (*targets, storage=None))]
+    fn status(&self, targets: &Bound<PyTuple>, storage: Option<String>) -> PyResult<FileStatus> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let entries = self.entries(get_targets(targets)?, None, false, false, true)?;
+        status::status(&xvc_root, &entries, storage)
+    }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["remote", "frm", "storage"],
-            "--storage",
-        )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-recheck"], "--no-recheck")?;
-        update_cli_opt(
+    /// Send the cached content of tracked files in `targets` to a storage.
+    ///
+    /// Files missing in the storage are sent with a single command. `progress` is called with a
+    /// [TransferEvent] when a file is skipped because it's already in the storage, when it's
+    /// started, and when it's found in the storage after the command or failed.
+    #[pyo3( signature = (*targets, options=None, progress=None, **opts))]
+    fn send(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<SendOptions>>,
+        progress: Option<Bound<PyAny>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], &["remote", "to", "storage"], &["force"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "send");
+        }
+
+        let storage: String = get_required(opts, &["storage", "remote", "to"])?;
+        let force = get_flag(opts, &["force"])?;
+        let targets = get_targets(targets)?;
+        let command = command_string("send", &targets);
+        let (xvc_root, files) = self.tracked_files(targets)?;
+        let storage_paths = if force {
+            StoragePaths::default()
+        } else {
+            store::storage_paths(&xvc_root, &storage)?
+        };
+
+        let mut progress = Progress::new("send", &storage, progress);
+        let mut batch = Vec::new();
+        for file in files {
+            if !file.is_cached() {
+                progress.failed(&file, "not in the cache")?;
+            } else if storage_paths.contains(&file.cache_path) {
+                progress.skipped(&file, "already in the storage")?;
+            } else {
+                batch.push(file);
+            }
+        }
+
+        if !batch.is_empty() {
+            for file in &batch {
+                progress.started(file)?;
+            }
+            let send_cli = SendCLI {
+                storage: parse_value(&storage, "storage")?,
+                force,
+                targets: Some(store::targets(&self.xvc_opts, &xvc_root, &batch)?),
+            };
+            let result = self.run_without_git(XvcFileSubCommand::Send(send_cli), command.clone());
+            let errors = BatchErrors::new(py, &result);
+            let sent = store::storage_paths(&xvc_root, &storage)?;
+            for file in &batch {
+                match errors.of(file) {
+                    None if sent.contains(&file.cache_path) => progress.finished(file)?,
+                    _ => progress.failed(file, &errors.reason(file, "not found in the storage"))?,
+                }
+            }
+        }
+        self.commit(command)?;
+        progress.finish().into_py_any(py)
+    }
+
+    /// Bring the content of tracked files in `targets` from a storage to the cache, and recheck
+    /// them unless `no_recheck` is set.
+    ///
+    /// Files larger than `max_size` (bytes, or a string like `"500M"`), matching one of the
+    /// `exclude` glob patterns or, with `only_missing`, existing in the workspace are left out.
+    /// With `dry_run`, the files that would be brought are reported and nothing is transferred.
+    ///
+    /// Files missing in the cache are brought with a single command for each recheck method.
+    /// `progress` is called with a [TransferEvent] when a file is skipped because it's already in
+    /// the cache, when it's started, and when it's found in the cache after the command or
+    /// failed.
+    #[pyo3( signature = (*targets, options=None, progress=None, **opts))]
+    fn bring(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<BringOptions>>,
+        progress: Option<Bound<PyAny>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["recheck-as", "recheck_as"],
-            "--recheck-as",
+            &[
+                &["help"],
+                &["remote", "frm", "storage"],
+                &["force"],
+                &["no-recheck", "no_recheck"],
+                &["recheck-as", "recheck_as"],
+                &["dry-run", "dry_run"],
+                &["max-size", "max_size"],
+                &["exclude"],
+                &["only-missing", "only_missing"],
+            ],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "bring");
+        }
+
+        let storage: String = get_required(opts, &["storage", "remote", "frm"])?;
+        let force = get_flag(opts, &["force"])?;
+        let no_recheck = get_flag(opts, &["no-recheck", "no_recheck"])?;
+        let recheck_as = get_string(opts, &["recheck-as", "recheck_as"])?;
+        let dry_run = get_flag(opts, &["dry-run", "dry_run"])?;
+        let filter = BringFilter {
+            max_size: get_string(opts, &["max-size", "max_size"])?
+                .map(|s| transfer::parse_size(&s))
+                .transpose()?,
+            exclude: get_values(opts, &["exclude"])?.unwrap_or_default(),
+            only_missing: get_flag(opts, &["only-missing", "only_missing"])?,
+        };
+        let targets = get_targets(targets)?;
+        let command = command_string("bring", &targets);
+        let (xvc_root, files) = self.tracked_files(targets)?;
+        let policy = Policy::load(&xvc_root)?;
+
+        let mut progress = Progress::new("bring", &storage, progress);
+        if dry_run {
+            progress.set_dry_run();
+        }
+        // Files to bring and files in the cache to recheck, by recheck method
+        let mut bring_batches = BTreeMap::<Option<String>, Vec<TrackedFile>>::new();
+        let mut recheck_batches = BTreeMap::<Option<String>, Vec<TrackedFile>>::new();
+        for file in files {
+            if let Some(reason) = filter.exclusion(py, &xvc_root, &file)? {
+                progress.excluded(&file, &reason)?;
+                continue;
+            }
+            let skipped = file.is_cached() && !force;
+            if dry_run {
+                if skipped {
+                    progress.skipped(&file, "already in the cache")?;
+                } else {
+                    progress.planned(&file)?;
+                }
+                continue;
+            }
+
+            let recheck_as = match &recheck_as {
+                Some(recheck_as) => Some(recheck_as.clone()),
+                None => policy
+                    .rule_for(py, &file.path)?
+                    .map(|rule| rule.recheck_method.clone()),
+            };
+            if skipped {
+                progress.skipped(&file, "already in the cache")?;
+                if !no_recheck && !file.xvc_path.to_absolute_path(&xvc_root).exists() {
+                    recheck_batches.entry(recheck_as).or_default().push(file);
+                }
+            } else {
+                bring_batches.entry(recheck_as).or_default().push(file);
+            }
+        }
+
+        let checked_out =
+            |file: &TrackedFile| no_recheck || file.xvc_path.to_absolute_path(&xvc_root).exists();
+        for (recheck_as, batch) in recheck_batches {
+            let recheck_cli = RecheckCLI {
+                recheck_method: recheck_as
+                    .as_deref()
+                    .map(|r| parse_value(r, "recheck_as"))
+                    .transpose()?,
+                force: false,
+                no_parallel: false,
+                targets: Some(store::targets(&self.xvc_opts, &xvc_root, &batch)?),
+            };
+            let result =
+                self.run_without_git(XvcFileSubCommand::Recheck(recheck_cli), command.clone());
+            let errors = BatchErrors::new(py, &result);
+            for file in &batch {
+                if errors.of(file).is_some() || !checked_out(file) {
+                    progress.failed(file, &errors.reason(file, "not checked out"))?;
+                }
+            }
+        }
+        for (recheck_as, batch) in bring_batches {
+            for file in &batch {
+                progress.started(file)?;
+            }
+            let bring_cli = BringCLI {
+                storage: parse_value(&storage, "storage")?,
+                force,
+                no_recheck,
+                recheck_as: recheck_as
+                    .as_deref()
+                    .map(|r| parse_value(r, "recheck_as"))
+                    .transpose()?,
+                targets: Some(store::targets(&self.xvc_opts, &xvc_root, &batch)?),
+            };
+            let result = self.run_without_git(XvcFileSubCommand::Bring(bring_cli), command.clone());
+            let errors = BatchErrors::new(py, &result);
+            for file in &batch {
+                match errors.of(file) {
+                    None if file.is_cached() && checked_out(file) => progress.finished(file)?,
+                    _ => progress.failed(file, &errors.reason(file, "not found in the cache"))?,
+                }
+            }
+        }
+        if !dry_run {
+            self.commit(command)?;
+        }
+        progress.finish().into_py_any(py)
     }
 
-    #[pyo3( signature = (source, destination, **opts))]
+    #[pyo3( signature = (source, destination, options=None, **opts))]
     fn copy(
         &self,
-        source: String,
-        destination: String,
+        source: PathBuf,
+        destination: PathBuf,
+        options: Option<&Bound<CopyOptions>>,
         opts: Option<&Bound<PyDict>>,
     ) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("copy".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["recheck-method", "recheck_method"],
-            "--recheck-method",
+            &[
+                &["help"],
+                &["recheck-method", "recheck_method"],
+                &["force"],
+                &["no-recheck", "no_recheck"],
+            ],
         )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-recheck"], "--no-recheck")?;
-        cli_opts.push(source);
-        cli_opts.push(destination);
-        self.run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help("copy");
+        }
+
+        let source = source.to_string_lossy().to_string();
+        let destination = destination.to_string_lossy().to_string();
+        let command = command_string("copy", &[source.clone(), destination.clone()]);
+        let copy_cli = CopyCLI {
+            recheck_method: get_parsed(opts, &["recheck-method", "recheck_method"])?,
+            force: get_flag(opts, &["force"])?,
+            no_recheck: get_flag(opts, &["no-recheck", "no_recheck"])?,
+            source,
+            destination,
+        };
+        self.run(XvcFileSubCommand::Copy(copy_cli), command)
     }
 
-    #[pyo3( signature = (source, destination, **opts))]
+    #[pyo3( signature = (source, destination, options=None, **opts))]
     fn mv(
         &self,
-        source: String,
-        destination: String,
+        source: PathBuf,
+        destination: PathBuf,
+        options: Option<&Bound<MoveOptions>>,
         opts: Option<&Bound<PyDict>>,
     ) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("move".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["recheck-method", "recheck_method"],
-            "--recheck-method",
+            &[
+                &["help"],
+                &["recheck-method", "recheck_method"],
+                &["force"],
+                &["no-recheck", "no_recheck"],
+            ],
         )?;
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-recheck"], "--no-recheck")?;
-        cli_opts.push(source);
-        cli_opts.push(destination);
-        self.run(cli_opts)
-    }
+        if get_flag(opts, &["help"])? {
+            return self.help("move");
+        }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn untrack(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("untrack".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        let source = source.to_string_lossy().to_string();
+        let destination = destination.to_string_lossy().to_string();
+        let command = command_string("move", &[source.clone(), destination.clone()]);
+        let move_cli = MoveCLI {
+            recheck_method: get_parsed(opts, &["recheck-method", "recheck_method"])?,
+            force: get_flag(opts, &["force"])?,
+            no_recheck: get_flag(opts, &["no-recheck", "no_recheck"])?,
+            source,
+            destination,
+        };
+        self.run(XvcFileSubCommand::Move(move_cli), command)
+    }
 
-        update_cli_opt(
+    /// Stop tracking the files in `targets`.
+    ///
+    /// With `restore_dir`, the versions selected by `versions`, a list of Git references and
+    /// digest prefixes, or all recorded versions if not given, are copied from the cache to
+    /// `restore_dir`. They are named with the `naming` format string, with the fields `path`,
+    /// `parent`, `name`, `stem`, `suffix`, `digest` and `short_digest`. The cache files are kept
+    /// unless `remove_cache=True`. Returns the list of [RestoredFile] objects.
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn untrack(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<UntrackOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["restore-versions", "restore_versions"],
-            "--recheck-method",
+            &[
+                &["help"],
+                &[
+                    "restore-dir",
+                    "restore_dir",
+                    "restore-versions",
+                    "restore_versions",
+                ],
+                &["versions"],
+                &["naming"],
+                &["remove-cache", "remove_cache"],
+            ],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
-    }
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "untrack");
+        }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn remove(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("remove".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        let restore_dir = get_string(
+            opts,
+            &[
+                "restore-dir",
+                "restore_dir",
+                "restore-versions",
+                "restore_versions",
+            ],
+        )?
+        .map(PathBuf::from);
+        let selectors = get_values(opts, &["versions"])?;
+        let naming =
+            get_string(opts, &["naming"])?.unwrap_or_else(|| untrack::DEFAULT_NAMING.to_string());
+        let remove_cache = get_flag(opts, &["remove-cache", "remove_cache"])?;
+        if selectors.is_some() && restore_dir.is_none() {
+            return Err(PyValueError::new_err("versions needs restore_dir"));
+        }
+
+        let targets = get_targets(targets)?;
+        let (xvc_root, files) = self.tracked_files(targets.clone())?;
+        let restored = match &restore_dir {
+            Some(dir) => {
+                let versions = untrack::select_versions(&xvc_root, &files, selectors)?;
+                untrack::restore(py, &xvc_root, &versions, dir, &naming)?
+            }
+            None => Vec::new(),
+        };
+        let command = command_string("untrack", &targets);
+        let untrack_cli = UntrackCLI {
+            restore_versions: None,
+            targets,
+        };
+        if remove_cache {
+            raise_on_error(&self.run(XvcFileSubCommand::Untrack(untrack_cli), command)?)?;
+            return restored.into_py_any(py);
+        }
+
+        // The cache files are put back before the changes are committed
+        let backup = untrack::CacheBackup::new(&xvc_root, &files)?;
+        let output = self.run_without_git(XvcFileSubCommand::Untrack(untrack_cli), command.clone());
+        backup.restore()?;
+        raise_on_error(&output?)?;
+        self.commit(command)?;
+        restored.into_py_any(py)
+    }
 
-        update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
-        update_cli_flag(
+    /// Remove versions of the tracked files in `targets` from the cache or a storage.
+    ///
+    /// Returns a [RemoveReport] of the removed files and reclaimed bytes. With `dry_run=True`,
+    /// nothing is removed and the report lists what would be.
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn remove(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<RemoveOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["from-cache", "from_cache"],
-            "--from-cache",
+            &[
+                &["help"],
+                &["force"],
+                &["from-cache", "from_cache"],
+                &["from-storage", "from_storage"],
+                &["all_versions", "all-versions"],
+                &["only_version", "only-version"],
+                &["dry-run", "dry_run"],
+            ],
         )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["from-storage", "from_storage"],
-            "--from-storage",
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "remove");
+        }
+
+        let force = get_flag(opts, &["force"])?;
+        let from_cache = get_flag(opts, &["from-cache", "from_cache"])?;
+        let from_storage = get_string(opts, &["from-storage", "from_storage"])?;
+        let all_versions = get_flag(opts, &["all_versions", "all-versions"])?;
+        let only_version = get_string(opts, &["only_version", "only-version"])?;
+        if !from_cache && from_storage.is_none() {
+            return Err(PyValueError::new_err(
+                "remove needs from_cache=True or from_storage=<storage>",
+            ));
+        }
+        let versions = match (all_versions, &only_version) {
+            (true, Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "all_versions and only_version can't be used together",
+                ))
+            }
+            (true, None) => remove::VersionSelection::All,
+            (false, Some(prefix)) => remove::VersionSelection::Prefix(prefix.clone()),
+            (false, None) => remove::VersionSelection::Current,
+        };
+
+        let targets = get_targets(targets)?;
+        let (xvc_root, files) = self.tracked_files(targets.clone())?;
+        let plan = remove::plan(
+            &xvc_root,
+            &files,
+            versions,
+            from_cache,
+            from_storage.as_deref(),
+            force,
         )?;
-        update_cli_flag(
+        if get_flag(opts, &["dry-run", "dry_run"])? {
+            return remove::dry_run(plan).into_py_any(py);
+        }
+
+        let command = command_string("remove", &targets);
+        let remove_cli = RemoveCLI {
+            force,
+            from_cache,
+            from_storage: from_storage
+                .as_deref()
+                .map(|s| parse_value(s, "from_storage"))
+                .transpose()?,
+            all_versions,
+            only_version,
+            targets,
+        };
+        let output = self.run(XvcFileSubCommand::Remove(remove_cli), command)?;
+        remove::report(
+            &xvc_root,
+            plan,
+            from_storage.as_deref(),
+            output_errors(&output),
+        )?
+        .into_py_any(py)
+    }
+
+    /// Create presigned URLs for the tracked files in `targets`.
+    ///
+    /// `duration` is a number of seconds or a duration like `24h` or `1h30m`, 24 hours by
+    /// default. Returns a list of [SharedUrl] objects, one for each file. When `manifest` is
+    /// given, the URLs are also written to it as JSON or CSV, chosen by its extension.
+    #[pyo3( signature = (*targets, options=None, **opts))]
+    fn share(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        options: Option<&Bound<ShareOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<PyObject> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["all_versions", "all-versions"],
-            "--all-versions",
+            &[
+                &["help"],
+                &["remote", "storage"],
+                &["duration"],
+                &["manifest"],
+            ],
         )?;
-        update_cli_opt(
+        if get_flag(opts, &["help"])? {
+            return self.help_object(py, "share");
+        }
+
+        let storage: String = get_required(opts, &["storage", "remote"])?;
+        let duration = get_string(opts, &["duration"])?.unwrap_or_else(|| "24h".to_string());
+        let seconds = share::parse_duration(&duration)?;
+        let manifest = get_string(opts, &["manifest"])?.map(PathBuf::from);
+
+        let (xvc_root, files) = self.tracked_files(get_targets(targets)?)?;
+        let mut urls = Vec::with_capacity(files.len());
+        for file in files {
+            // Computed before the URL is signed, so the URL is valid at least until then
+            let expires_at = share::expiration_time(py, seconds)?;
+            let target = store::target(&self.xvc_opts, &xvc_root, &file.path)?;
+            let command = command_string("share", &[target.clone()]);
+            let share_cli = ShareCLI {
+                storage: parse_value(&storage, "storage")?,
+                duration: format!("{}s", seconds),
+                target,
+            };
+            let output = self.run_without_git(XvcFileSubCommand::Share(share_cli), command)?;
+            raise_on_error(&output)?;
+            let url = share::parse_url(&output).ok_or_else(|| {
+                PyRuntimeError::new_err(format!(
+                    "storage '{}' didn't return a URL for '{}'",
+                    storage, file.path
+                ))
+            })?;
+            urls.push(SharedUrl {
+                path: file.path.clone(),
+                digest: file.digest.digest().hex_str(),
+                url,
+                storage: storage.clone(),
+                expires_at,
+            });
+        }
+
+        if let Some(manifest) = manifest {
+            share::write_manifest(py, &urls, &manifest)?;
+        }
+        urls.into_py_any(py)
+    }
+
+    /// Open the version of `path` recorded at the Git reference `ref` as a read-only file object.
+    ///
+    /// The file is read from the cache, the workspace isn't modified. When the version isn't in
+    /// the cache and it's also the version recorded in the workspace, it's brought from `storage`
+    /// without recheck.
+    #[pyo3(signature = (path, r#ref=None, mode="rb", storage=None))]
+    fn open(
+        &self,
+        py: Python,
+        path: PathBuf,
+        r#ref: Option<String>,
+        mode: &str,
+        storage: Option<String>,
+    ) -> PyResult<PyObject> {
+        if !matches!(mode, "r" | "rb" | "rt") {
+            return Err(PyValueError::new_err(format!(
+                "invalid mode '{}', tracked files can only be opened for reading",
+                mode
+            )));
+        }
+
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        let commit = match &r#ref {
+            Some(r) => Some(store::resolve_ref(&xvc_root, r)?),
+            None => None,
+        };
+        let records = Records::load(&xvc_root, commit.as_deref())?;
+        let digest = records.digest(&xvc_path).ok_or_else(|| {
+            PyFileNotFoundError::new_err(match &r#ref {
+                Some(r) => format!("'{}' is not tracked at '{}'", xvc_path, r),
+                None => format!("'{}' is not tracked", xvc_path),
+            })
+        })?;
+        let cache_path = store::cache_path(&xvc_root, &xvc_path, &digest)?;
+
+        if !cache_path.exists() {
+            let current = match commit {
+                Some(_) => Records::load(&xvc_root, None)?.digest(&xvc_path),
+                None => Some(digest.clone()),
+            };
+            match storage {
+                Some(storage) if current.as_ref() == Some(&digest) => {
+                    let target = store::target(&self.xvc_opts, &xvc_root, &xvc_path.to_string())?;
+                    let command = command_string("bring", &[target.clone()]);
+                    let bring_cli = BringCLI {
+                        storage: parse_value(&storage, "storage")?,
+                        force: false,
+                        no_recheck: true,
+                        recheck_as: None,
+                        targets: Some(vec![target]),
+                    };
+                    raise_on_error(&self.run(XvcFileSubCommand::Bring(bring_cli), command)?)?;
+                }
+                Some(_) => {
+                    return Err(PyFileNotFoundError::new_err(format!(
+                        "the version of '{}' at '{}' is not in the cache and only the version \
+                         recorded in the workspace can be brought",
+                        xvc_path,
+                        r#ref.unwrap_or_default()
+                    )))
+                }
+                None => {}
+            }
+        }
+        if !cache_path.exists() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "'{}' is not in the cache, bring it from a storage first",
+                xvc_path
+            )));
+        }
+
+        py.import("io")?
+            .call_method1("open", (cache_path, mode))
+            .map(Bound::unbind)
+    }
+
+    /// Versions of `path` recorded in the Git history, newest first
+    fn history(&self, path: PathBuf) -> PyResult<Vec<FileVersion>> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        history::history(&xvc_root, &xvc_path)
+    }
+
+    /// Compare the versions of `path` recorded at `ref_a` and `ref_b`. The records in the
+    /// workspace are used when `ref_b` is not given.
+    #[pyo3(signature = (path, ref_a, ref_b=None))]
+    fn diff(&self, path: PathBuf, ref_a: &str, ref_b: Option<&str>) -> PyResult<FileDiff> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        history::diff(&xvc_root, &xvc_path, ref_a, ref_b)
+    }
+
+    /// Write `data` to `path` in the workspace and track it.
+    ///
+    /// `data` can be `bytes`, an object supporting the buffer protocol like a NumPy array, a
+    /// binary file-like object, an iterable of chunks, or a pandas or Polars data frame for
+    /// `.parquet` and `.csv` paths. Existing files are replaced only with `force=True`.
+    ///
+    /// This is the same as writing the file and calling [XvcFile::track], and takes the same
+    /// options. The recheck method policy applies when no `recheck_method` is given. The digest is calculated by `track` from the written file, not while writing:
+    /// the configured algorithm and the text or binary detection, which changes line endings
+    /// before hashing, are applied only there, so the recorded digest is always the one `track`
+    /// would record. Returns that digest.
+    #[pyo3(signature = (path, data, options=None, **opts))]
+    fn put(
+        &self,
+        py: Python,
+        path: PathBuf,
+        data: &Bound<PyAny>,
+        options: Option<&Bound<TrackOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<Digest> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["only_version", "only-version"],
-            "--only-version",
+            &[
+                &["recheck-method", "recheck_method"],
+                &["no-commit", "no_commit"],
+                &["text-or-binary", "text_or_binary"],
+                &["force"],
+                &["no-parallel", "no_parallel"],
+                &["include-git-files", "include_git_files"],
+            ],
         )?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
+        let force = get_flag(opts, &["force"])?;
+
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        put::write(data, &store::workdir_path(&self.xvc_opts, &path)?, force)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+
+        let target = path.to_string_lossy().to_string();
+        let command = command_string("track", &[target.clone()]);
+        let recheck_method = get_parsed(opts, &["recheck-method", "recheck_method"])?;
+        let use_policy = recheck_method.is_none() && !self.policy_rules()?.is_empty();
+        let track_cli = TrackCLI {
+            recheck_method,
+            no_commit: get_flag(opts, &["no-commit", "no_commit"])?,
+            text_or_binary: get_parsed(opts, &["text-or-binary", "text_or_binary"])?,
+            force,
+            no_parallel: get_flag(opts, &["no-parallel", "no_parallel"])?,
+            include_git_files: get_flag(opts, &["include-git-files", "include_git_files"])?,
+            targets: Some(vec![target.clone()]),
+        };
+        raise_on_error(
+            &self.run_without_git(XvcFileSubCommand::Track(track_cli), command.clone())?,
+        )?;
+        if use_policy {
+            policy::apply(py, self, vec![target])?;
+        }
+        self.commit(command)?;
+
+        Records::load(&xvc_root, None)?
+            .digest(&xvc_path)
+            .map(|d| Digest::from(&d))
+            .ok_or_else(|| PyRuntimeError::new_err(format!("'{}' is not tracked", xvc_path)))
     }
 
-    #[pyo3( signature = (*targets, **opts))]
-    fn share(&self, targets: &Bound<PyTuple>, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("share".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    /// Groups of tracked files in `targets`, or in the whole workspace, with identical content.
+    ///
+    /// The report includes the space saved by files rechecked as links and the space that could be
+    /// saved by rechecking copies as links.
+    #[pyo3(signature = (*targets))]
+    fn duplicates(&self, targets: &Bound<PyTuple>) -> PyResult<DuplicateReport> {
+        let (_, files) = self.tracked_files(get_targets(targets)?)?;
+        Ok(duplicates::duplicates(files))
+    }
 
-        update_cli_opt(opts, &mut cli_opts, &["remote", "storage"], "--storage")?;
-        update_cli_opt(opts, &mut cli_opts, &["duration"], "--duration")?;
-        update_targets(targets, &mut cli_opts)?;
-        self.run(cli_opts)
+    /// The recheck method policy, rules mapping path patterns to the recheck methods `track`,
+    /// `recheck` and `bring` use when no method is given
+    fn policy(&self) -> RecheckPolicy {
+        RecheckPolicy::new(self.clone())
+    }
+
+    /// Set labels of the tracked file at `path` from keyword arguments, e.g.
+    /// `annotate("data.csv", split="train", license="CC-BY-4.0")`.
+    ///
+    /// Values are converted to strings and `None` removes a label. Labels are committed with the
+    /// other records. Returns all labels of the file.
+    #[pyo3(signature = (path, **labels))]
+    fn annotate(
+        &self,
+        path: PathBuf,
+        labels: Option<&Bound<PyDict>>,
+    ) -> PyResult<BTreeMap<String, String>> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        let labels = labels::annotate(&xvc_root, &xvc_path, labels::label_values(labels)?)?;
+        self.commit(command_string("annotate", &[xvc_path.to_string()]))?;
+        Ok(labels)
+    }
+
+    /// Labels of the tracked file at `path`
+    fn labels(&self, path: PathBuf) -> PyResult<BTreeMap<String, String>> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        Ok(labels::labels_by_path(&xvc_root)?
+            .remove(&xvc_path.to_string())
+            .unwrap_or_default())
+    }
+
+    /// Download `url` to `path` and track it, recording the URL with the `ETag` and
+    /// `Last-Modified` headers of the response. Without `recheck_method`, the rules of
+    /// [XvcFile::policy] are used like in [XvcFile::track].
+    #[pyo3(signature = (url, path, **opts))]
+    fn track_url(
+        &self,
+        py: Python,
+        url: String,
+        path: PathBuf,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<TrackedUrl> {
+        check_opts(opts, &[&["recheck-method", "recheck_method"]])?;
+        url::check_url(&url)?;
+        let file = store::workdir_path(&self.xvc_opts, &path)?;
+        if file.exists() {
+            return Err(PyFileExistsError::new_err(format!(
+                "{} exists, use refresh_urls() to update files tracked from URLs",
+                path.to_string_lossy()
+            )));
+        }
+        let recheck_method = get_parsed(opts, &["recheck-method", "recheck_method"])?;
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let xvc_path = store::xvc_path(&self.xvc_opts, &xvc_root, &path)?;
+        let client = url::client()?;
+        let download = py
+            .allow_threads(|| url::download(&client, &url, &file, None))
+            .map_err(PyRuntimeError::new_err)?
+            .ok_or_else(|| {
+                PyRuntimeError::new_err(format!("{} returned no content to download", url))
+            })?;
+        let source = download.replace(&file)?;
+
+        let targets = vec![path.to_string_lossy().to_string()];
+        let command = command_string("track-url", &targets);
+        let use_policy = recheck_method.is_none();
+        let track_cli = TrackCLI {
+            recheck_method,
+            no_commit: false,
+            text_or_binary: None,
+            force: false,
+            no_parallel: false,
+            include_git_files: false,
+            targets: Some(targets.clone()),
+        };
+        let tracked = || -> PyResult<()> {
+            raise_on_error(
+                &self.run_without_git(XvcFileSubCommand::Track(track_cli), command.clone())?,
+            )?;
+            if use_policy {
+                policy::apply(py, self, targets)?;
+            }
+            url::record(&xvc_root, &xvc_path, source.clone())
+        };
+        // The downloaded file isn't left in the workspace untracked
+        if let Err(e) = tracked() {
+            let _ = fs::remove_file(&file);
+            return Err(e);
+        }
+        self.commit(command)?;
+        Ok(TrackedUrl::new(xvc_path.to_string(), source))
+    }
+
+    /// Files in `targets`, or in the whole workspace, tracked from URLs. With `check`, the
+    /// headers of the URLs are compared with the recorded ones to set `changed`, without
+    /// downloading the content.
+    #[pyo3(signature = (*targets, check=true))]
+    fn urls(&self, py: Python, targets: &Bound<PyTuple>, check: bool) -> PyResult<Vec<TrackedUrl>> {
+        let (xvc_root, files) = self.tracked_files(get_targets(targets)?)?;
+        let sources: Vec<(String, UrlSource)> = url::sources(&xvc_root, files)?
+            .into_iter()
+            .map(|(file, source)| (file.path, source))
+            .collect();
+        let client = url::client()?;
+        Ok(py.allow_threads(|| {
+            sources
+                .into_iter()
+                .map(|(path, source)| {
+                    let mut tracked = TrackedUrl::new(path, source.clone());
+                    if check {
+                        match url::check(&client, &source) {
+                            Ok(changed) => tracked.changed = changed,
+                            Err(e) => tracked.error = Some(e),
+                        }
+                    }
+                    tracked
+                })
+                .collect()
+        }))
+    }
+
+    /// Download the files in `targets`, or in the whole workspace, tracked from URLs again and
+    /// carry in those whose content changed. Requests are conditional on the recorded headers.
+    /// Returns the checked files with `changed` set, or `error` if the download or the comparison
+    /// with the file failed.
+    #[pyo3(signature = (*targets))]
+    fn refresh_urls(&self, py: Python, targets: &Bound<PyTuple>) -> PyResult<Vec<TrackedUrl>> {
+        let (xvc_root, files) = self.tracked_files(get_targets(targets)?)?;
+        let root = xvc_root.absolute_path().to_path_buf();
+        let mut results = Vec::new();
+        let mut changed = Vec::new();
+        let mut updated = Vec::new();
+        let client = url::client()?;
+        for (file, source) in url::sources(&xvc_root, files)? {
+            let path = root.join(&file.path);
+            let mut tracked = TrackedUrl::new(file.path.clone(), source.clone());
+            match py.allow_threads(|| url::download(&client, &source.url, &path, Some(&source))) {
+                Ok(None) => tracked.changed = Some(false),
+                Ok(Some(download)) => {
+                    let current = download.source.clone();
+                    let same_content = if path.exists() {
+                        download.same_content(&path)
+                    } else {
+                        Ok(false)
+                    };
+                    let same_content = match same_content {
+                        Ok(same_content) => same_content,
+                        Err(e) => {
+                            download.discard();
+                            tracked.error = Some(e.to_string());
+                            results.push(tracked);
+                            continue;
+                        }
+                    };
+                    if same_content {
+                        download.discard();
+                        tracked.changed = Some(false);
+                    } else {
+                        download.replace(&path)?;
+                        tracked.changed = Some(true);
+                        changed.push(store::target(&self.xvc_opts, &xvc_root, &file.path)?);
+                    }
+                    if current != source {
+                        tracked.etag = current.etag.clone();
+                        tracked.last_modified = current.last_modified.clone();
+                        updated.push((file.xvc_path, current));
+                    }
+                }
+                Err(e) => tracked.error = Some(e),
+            }
+            results.push(tracked);
+        }
+
+        if !changed.is_empty() {
+            let carry_in_cli = CarryInCLI {
+                text_or_binary: None,
+                force: false,
+                no_parallel: false,
+                targets: Some(changed.clone()),
+            };
+            raise_on_error(&self.run_without_git(
+                XvcFileSubCommand::CarryIn(carry_in_cli),
+                command_string("carry-in", &changed),
+            )?)?;
+        }
+        if !changed.is_empty() || !updated.is_empty() {
+            let paths: Vec<String> = results.iter().map(|r| r.path.clone()).collect();
+            for (xvc_path, source) in updated {
+                url::record(&xvc_root, &xvc_path, source)?;
+            }
+            self.commit(command_string("refresh-urls", &paths))?;
+        }
+        Ok(results)
+    }
+
+    /// Check the tracked files in `targets`, or in the whole workspace, against their records.
+    ///
+    /// Cache files must exist and symlinks and hardlinks must point to them. With `deep`, cache
+    /// files and copies are hashed and compared with the recorded digests, otherwise copies are
+    /// compared by size. Files are hashed with the text or binary setting they were tracked with,
+    /// or `text_or_binary` if given. With `repair`, missing and corrupted cache files are brought
+    /// from `storage` and files are checked out again, replacing copies edited in place. Files
+    /// that can't be hashed are reported as `hash_error` and left as they are.
+    #[pyo3(signature = (*targets, deep=true, repair=false, storage=None, **opts))]
+    fn verify(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        deep: bool,
+        repair: bool,
+        storage: Option<String>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<VerifyReport> {
+        check_opts(opts, &[&["text-or-binary", "text_or_binary"]])?;
+        let (xvc_root, files) = self.tracked_files(get_targets(targets)?)?;
+        let text_or_binary = verify::text_or_binary(
+            &xvc_root,
+            &files,
+            get_parsed(opts, &["text-or-binary", "text_or_binary"])?,
+        )?;
+        let root = xvc_root.absolute_path().to_path_buf();
+        let mut report = VerifyReport {
+            checked: files.len(),
+            deep,
+            issues: verify::check(py, &root, &files, deep, &text_or_binary),
+        };
+        if !repair || report.issues.is_empty() {
+            return Ok(report);
+        }
+
+        let mut from_storage = Vec::new();
+        let mut recheck = Vec::new();
+        for issue in report.issues.iter_mut() {
+            // Content that can't be read may still be valid, so it isn't replaced
+            if issue.is_hash_error() {
+                continue;
+            }
+            let target = store::target(&self.xvc_opts, &xvc_root, &issue.path)?;
+            if issue.is_in_cache() {
+                if storage.is_none() {
+                    issue.error = Some("no storage to bring the content from".to_string());
+                    continue;
+                }
+                verify::remove_cache_file(issue)?;
+                if !from_storage.contains(&target) {
+                    from_storage.push(target.clone());
+                }
+            }
+            if !recheck.contains(&target) {
+                recheck.push(target);
+            }
+        }
+        if let (Some(storage), false) = (&storage, from_storage.is_empty()) {
+            let bring_cli = BringCLI {
+                storage: parse_value(storage, "storage")?,
+                force: false,
+                no_recheck: true,
+                recheck_as: None,
+                targets: Some(from_storage.clone()),
+            };
+            let command = command_string("bring", &from_storage);
+            if let Err(e) = self
+                .run_without_git(XvcFileSubCommand::Bring(bring_cli), command)
+                .and_then(|output| raise_on_error(&output))
+            {
+                for issue in report.issues.iter_mut().filter(|i| i.is_in_cache()) {
+                    issue.error = Some(e.to_string());
+                }
+            }
+        }
+        if !recheck.is_empty() {
+            let recheck_cli = RecheckCLI {
+                recheck_method: None,
+                force: true,
+                no_parallel: false,
+                targets: Some(recheck.clone()),
+            };
+            raise_on_error(&self.run_without_git(
+                XvcFileSubCommand::Recheck(recheck_cli),
+                command_string("recheck", &recheck),
+            )?)?;
+            self.commit(command_string("verify", &recheck))?;
+        }
+
+        let repaired: HashSet<&str> = report.issues.iter().map(|i| i.path.as_str()).collect();
+        let files: Vec<TrackedFile> = files
+            .into_iter()
+            .filter(|f| repaired.contains(f.path.as_str()))
+            .collect();
+        let remaining = verify::check(py, &root, &files, deep, &text_or_binary);
+        for issue in report.issues.iter_mut() {
+            issue.repaired = !issue.is_hash_error()
+                && !remaining
+                    .iter()
+                    .any(|r| r.path == issue.path && r.kind == issue.kind);
+        }
+        Ok(report)
+    }
+
+    /// Tracked files matching `glob` as a lazy [Dataset].
+    ///
+    /// Items are brought from `storage` to the cache when they are accessed. A single fetch
+    /// thread brings up to `batch_size` queued items with each command, so commands don't run
+    /// concurrently on the repository; batches are fetched one after another. Accessing an item
+    /// also queues the next `prefetch` items. Without a storage, only the files in the cache can
+    /// be accessed.
+    #[pyo3(signature = (glob, storage=None, prefetch=8, batch_size=4))]
+    fn dataset(
+        &self,
+        glob: PathBuf,
+        storage: Option<String>,
+        prefetch: usize,
+        batch_size: usize,
+    ) -> PyResult<Dataset> {
+        let (_, files) = self.tracked_files(vec![glob.to_string_lossy().to_string()])?;
+        Ok(Dataset::new(
+            self.clone(),
+            files,
+            storage,
+            prefetch,
+            batch_size,
+        ))
     }
 }
@@ -0,0 +1,86 @@
+//! Reading in-memory data passed from Python.
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyString};
+
+/// Chunk size used to read file-like objects
+const READ_CHUNK_SIZE: usize = 1 << 20;
+
+fn type_name(value: &Bound<PyAny>) -> String {
+    value
+        .get_type()
+        .name()
+        .map(|n| n.to_string())
+        .unwrap_or_default()
+}
+
+/// Call `f` with the bytes of a buffer-protocol object, returns `None` if `value` doesn't support
+/// the buffer protocol
+fn with_buffer<F>(value: &Bound<PyAny>, f: &mut F) -> PyResult<Option<()>>
+where
+    F: FnMut(&[u8]) -> PyResult<()>,
+{
+    if let Ok(bytes) = value.downcast::<PyBytes>() {
+        return f(bytes.as_bytes()).map(Some);
+    }
+    if let Ok(bytearray) = value.downcast::<PyByteArray>() {
+        return f(&bytearray.to_vec()).map(Some);
+    }
+    let py = value.py();
+    let Ok(view) = py.import("builtins")?.call_method1("memoryview", (value,)) else {
+        return Ok(None);
+    };
+    // Non-contiguous buffers, like sliced arrays, are copied in C order
+    let bytes = view.call_method0("tobytes")?;
+    f(bytes.downcast::<PyBytes>()?.as_bytes()).map(Some)
+}
+
+/// Call `f` with the content of `data` in chunks.
+///
+/// `data` is an object supporting the buffer protocol, like `bytes`, `bytearray`, `memoryview` or
+/// a NumPy array, a binary file-like object with a `read` method, or an iterable of buffer
+/// objects.
+pub fn for_each_chunk<F>(data: &Bound<PyAny>, mut f: F) -> PyResult<()>
+where
+    F: FnMut(&[u8]) -> PyResult<()>,
+{
+    if data.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err(
+            "data must be bytes or a binary file-like object, encode str before passing it",
+        ));
+    }
+    if with_buffer(data, &mut f)?.is_some() {
+        return Ok(());
+    }
+
+    if data.hasattr("read")? {
+        loop {
+            let chunk = data.call_method1("read", (READ_CHUNK_SIZE,))?;
+            let chunk = chunk.downcast::<PyBytes>().map_err(|_| {
+                PyTypeError::new_err("file-like objects must be opened in binary mode")
+            })?;
+            if chunk.as_bytes().is_empty() {
+                return Ok(());
+            }
+            f(chunk.as_bytes())?;
+        }
+    }
+
+    let chunks = data.try_iter().map_err(|_| {
+        PyTypeError::new_err(format!(
+            "data must be bytes, a buffer, a binary file-like object or an iterable of bytes, got '{}'",
+            type_name(data)
+        ))
+    })?;
+    for chunk in chunks {
+        let chunk = chunk?;
+        if chunk.is_instance_of::<PyString>() || with_buffer(&chunk, &mut f)?.is_none() {
+            return Err(PyTypeError::new_err(format!(
+                "chunks must be bytes or buffers, got '{}'",
+                type_name(&chunk)
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,397 @@
+//! Lazy datasets of tracked files, brought from a storage when they are accessed.
+//!
+//! A fetch thread brings the queued files to the cache, with a single `bring` command for the
+//! files queued together, so commands never run concurrently on the same repository. Accessing
+//! an item also queues the next `prefetch` items, so sequential reads find them in the cache.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use pyo3::exceptions::{
+    PyFileNotFoundError, PyIndexError, PyRuntimeError, PyRuntimeWarning, PyValueError,
+};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use xvc_rust::file::{bring::BringCLI, XvcFileSubCommand};
+
+use super::store::{self, TrackedFile};
+use super::transfer::BatchErrors;
+use super::{command_string, XvcFile};
+use crate::parse_value;
+
+/// An item of a [Dataset], available in the cache
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct DatasetItem {
+    pub index: usize,
+    /// Path of the file relative to the repository root
+    pub path: String,
+    pub digest: String,
+    pub size: Option<u64>,
+    /// Absolute path of the content in the cache, to be read only
+    pub local_path: String,
+}
+
+#[pymethods]
+impl DatasetItem {
+    /// Open the content of the item in the cache as a read-only file object
+    #[pyo3(signature = (mode="rb"))]
+    fn open<'py>(&self, py: Python<'py>, mode: &str) -> PyResult<Bound<'py, PyAny>> {
+        if !matches!(mode, "r" | "rb" | "rt") {
+            return Err(PyValueError::new_err(format!(
+                "invalid mode '{}', dataset items can only be opened for reading",
+                mode
+            )));
+        }
+        py.import("io")?
+            .call_method1("open", (&self.local_path, mode))
+    }
+
+    /// Read the content of the item
+    fn read(&self) -> PyResult<Vec<u8>> {
+        Ok(std::fs::read(&self.local_path)?)
+    }
+
+    fn __fspath__(&self) -> String {
+        self.local_path.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DatasetItem(index={}, path={:?})", self.index, self.path)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum FetchState {
+    Queued,
+    Done,
+    Failed(String),
+}
+
+type FetchStates = Arc<(Mutex<HashMap<usize, FetchState>>, Condvar)>;
+
+/// Bring the files at `indices` to the cache with a single command, without rechecking them
+fn fetch(
+    xvc_file: &XvcFile,
+    storage: &str,
+    files: &[TrackedFile],
+    indices: &[usize],
+) -> Vec<(usize, FetchState)> {
+    let missing: Vec<&TrackedFile> = indices
+        .iter()
+        .map(|i| &files[*i])
+        .filter(|f| !f.is_cached())
+        .collect();
+    let errors = if missing.is_empty() {
+        None
+    } else {
+        let xvc = &xvc_file.xvc_opts;
+        let targets = store::xvc_root(xvc)
+            .and_then(|xvc_root| store::targets(xvc, &xvc_root, missing.iter().copied()));
+        let result = targets.and_then(|targets| {
+            let command = command_string("bring", &targets);
+            let storage = parse_value(storage, "storage")?;
+            let bring_cli = BringCLI {
+                storage,
+                force: false,
+                no_recheck: true,
+                recheck_as: None,
+                targets: Some(targets),
+            };
+            xvc_file.run_without_git(XvcFileSubCommand::Bring(bring_cli), command)
+        });
+        Some(Python::with_gil(|py| BatchErrors::new(py, &result)))
+    };
+
+    indices
+        .iter()
+        .map(|i| {
+            let file = &files[*i];
+            let state = if file.is_cached() {
+                FetchState::Done
+            } else {
+                let reason = format!("'{}' is not in the storage", file.path);
+                FetchState::Failed(match &errors {
+                    Some(errors) => errors.reason(file, &reason),
+                    None => reason,
+                })
+            };
+            (*i, state)
+        })
+        .collect()
+}
+
+/// Bring the queued items until the queue is closed, up to `batch_size` items with each command
+fn fetch_queued(
+    xvc_file: &XvcFile,
+    storage: &str,
+    files: &[TrackedFile],
+    states: &FetchStates,
+    queued: Receiver<usize>,
+    batch_size: usize,
+) {
+    while let Ok(index) = queued.recv() {
+        let mut indices = vec![index];
+        indices.extend(queued.try_iter().take(batch_size - 1));
+        let results = fetch(xvc_file, storage, files, &indices);
+        let (lock, changed) = &**states;
+        lock.lock().expect("lock fetch states").extend(results);
+        changed.notify_all();
+    }
+}
+
+/// Tracked files matching a glob, brought from a storage on access.
+///
+/// Supports `len()`, indexing with ints and iteration. Items are [DatasetItem] objects whose
+/// content is in the cache. `close()`, or leaving a `with` block, stops the fetch thread and
+/// commits the records of brought files. Datasets that aren't closed do this when they are
+/// garbage collected.
+#[pyclass(module = "xvc")]
+pub struct Dataset {
+    xvc_file: Arc<XvcFile>,
+    files: Arc<Vec<TrackedFile>>,
+    /// The storage files are brought from, `None` to use only the cache
+    #[pyo3(get)]
+    storage: Option<String>,
+    /// Number of items queued after the accessed one
+    #[pyo3(get)]
+    prefetch: usize,
+    states: FetchStates,
+    queue: Mutex<Option<Sender<usize>>>,
+    fetcher: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dataset {
+    /// A dataset of `files`. Up to `batch_size` queued items are brought from `storage` with a
+    /// single command.
+    pub fn new(
+        xvc_file: XvcFile,
+        files: Vec<TrackedFile>,
+        storage: Option<String>,
+        prefetch: usize,
+        batch_size: usize,
+    ) -> Self {
+        let xvc_file = Arc::new(xvc_file);
+        let files = Arc::new(files);
+        let states: FetchStates = Arc::new((Mutex::new(HashMap::new()), Condvar::new()));
+        let (queue, queued) = unbounded::<usize>();
+
+        let fetcher = storage.clone().map(|storage| {
+            let (xvc_file, files, states) = (xvc_file.clone(), files.clone(), states.clone());
+            std::thread::spawn(move || {
+                fetch_queued(
+                    &xvc_file,
+                    &storage,
+                    &files,
+                    &states,
+                    queued,
+                    batch_size.max(1),
+                )
+            })
+        });
+
+        let queue = storage.as_ref().map(|_| queue);
+        Self {
+            xvc_file,
+            files,
+            storage,
+            prefetch,
+            states,
+            queue: Mutex::new(queue),
+            fetcher: Mutex::new(fetcher),
+        }
+    }
+
+    /// Close the queue and take the fetch thread to join, `None` if the dataset is closed
+    fn stop(&self) -> Option<Option<JoinHandle<()>>> {
+        let queue = self.queue.lock().expect("lock queue").take()?;
+        drop(queue);
+        Some(self.fetcher.lock().expect("lock fetcher").take())
+    }
+
+    /// Forget the items left in the queue and commit the records of the brought files
+    fn finish(&self) -> PyResult<()> {
+        let (lock, changed) = &*self.states;
+        lock.lock()
+            .expect("lock fetch states")
+            .retain(|_, state| !matches!(state, FetchState::Queued));
+        changed.notify_all();
+        self.xvc_file.commit(command_string("bring", &self.paths()))
+    }
+
+    /// Queue `index` to be brought, unless it's already queued
+    fn queue(&self, index: usize) {
+        if index >= self.files.len() {
+            return;
+        }
+        let queue = self.queue.lock().expect("lock queue");
+        let Some(queue) = queue.as_ref() else {
+            return;
+        };
+        let mut states = self.states.0.lock().expect("lock fetch states");
+        if let std::collections::hash_map::Entry::Vacant(e) = states.entry(index) {
+            e.insert(FetchState::Queued);
+            let _ = queue.send(index);
+        }
+    }
+
+    fn item(&self, py: Python, index: usize) -> PyResult<DatasetItem> {
+        let file = &self.files[index];
+        let item = DatasetItem {
+            index,
+            path: file.path.clone(),
+            digest: file.digest.digest().hex_str(),
+            size: file.size,
+            local_path: file.cache_file.to_string_lossy().to_string(),
+        };
+
+        for i in index + 1..=index + self.prefetch {
+            self.queue(i);
+        }
+        if file.is_cached() {
+            return Ok(item);
+        }
+        if self.storage.is_none() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "'{}' is not in the cache and the dataset has no storage",
+                file.path
+            )));
+        }
+
+        self.queue(index);
+        let state = py.allow_threads(|| {
+            let (lock, changed) = &*self.states;
+            let mut states = lock.lock().expect("lock fetch states");
+            loop {
+                match states.get(&index) {
+                    Some(FetchState::Queued) => {
+                        states = changed.wait(states).expect("wait fetch states");
+                    }
+                    Some(state) => return state.clone(),
+                    None => return FetchState::Failed("the dataset is closed".to_string()),
+                }
+            }
+        });
+        match state {
+            FetchState::Failed(e) => {
+                // Allow retrying on the next access
+                self.states
+                    .0
+                    .lock()
+                    .expect("lock fetch states")
+                    .remove(&index);
+                Err(PyRuntimeError::new_err(e))
+            }
+            _ => Ok(item),
+        }
+    }
+}
+
+#[pymethods]
+impl Dataset {
+    fn __len__(&self) -> usize {
+        self.files.len()
+    }
+
+    fn __getitem__(&self, py: Python, index: isize) -> PyResult<DatasetItem> {
+        let len = self.files.len() as isize;
+        let i = if index < 0 { len + index } else { index };
+        if i < 0 || i >= len {
+            return Err(PyIndexError::new_err("dataset index out of range"));
+        }
+        self.item(py, i as usize)
+    }
+
+    fn __iter__(slf: Py<Self>) -> DatasetIterator {
+        DatasetIterator {
+            dataset: slf,
+            index: 0,
+        }
+    }
+
+    /// Paths of the items, relative to the repository root
+    #[getter]
+    fn paths(&self) -> Vec<String> {
+        self.files.iter().map(|f| f.path.clone()).collect()
+    }
+
+    /// Stop the fetch thread and commit the records of the brought files
+    fn close(&self, py: Python) -> PyResult<()> {
+        let Some(fetcher) = self.stop() else {
+            return Ok(());
+        };
+        if let Some(fetcher) = fetcher {
+            py.allow_threads(|| {
+                let _ = fetcher.join();
+            });
+        }
+        self.finish()
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python, _args: &Bound<PyTuple>) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Dataset(items={}, storage={:?}, prefetch={})",
+            self.files.len(),
+            self.storage,
+            self.prefetch
+        )
+    }
+}
+
+impl Drop for Dataset {
+    fn drop(&mut self) {
+        let Some(fetcher) = self.stop() else {
+            return;
+        };
+        Python::with_gil(|py| {
+            // The fetch thread takes the GIL to read errors
+            if let Some(fetcher) = fetcher {
+                py.allow_threads(|| {
+                    let _ = fetcher.join();
+                });
+            }
+            if let Err(e) = self.finish() {
+                let message = CString::new(format!("the dataset could not be committed: {}", e))
+                    .unwrap_or_default();
+                let category = py.get_type::<PyRuntimeWarning>();
+                let _ = PyErr::warn(py, &category, &message, 1);
+            }
+        });
+    }
+}
+
+#[pyclass(module = "xvc")]
+pub struct DatasetIterator {
+    dataset: Py<Dataset>,
+    index: usize,
+}
+
+#[pymethods]
+impl DatasetIterator {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<DatasetItem>> {
+        let dataset = self.dataset.borrow(py);
+        if self.index >= dataset.files.len() {
+            return Ok(None);
+        }
+        let item = dataset.item(py, self.index)?;
+        self.index += 1;
+        Ok(Some(item))
+    }
+}
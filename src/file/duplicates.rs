@@ -0,0 +1,169 @@
+//! Tracked files with identical content.
+//!
+//! Files are grouped by their recorded digest. A file rechecked as a copy takes its size in the
+//! workspace in addition to the cache, while symlinks, hardlinks and reflinks share the cached
+//! content.
+
+use std::collections::{BTreeMap, HashSet};
+
+use pyo3::prelude::*;
+use xvc_rust::core::RecheckMethod as XvcRecheckMethod;
+
+use super::store::TrackedFile;
+
+/// Tracked paths with the same content
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct DuplicateGroup {
+    /// Hex digest of the content
+    pub digest: String,
+    /// Size of the content in bytes
+    pub size: u64,
+    pub paths: Vec<String>,
+    /// Recheck method of each path in `paths`
+    pub recheck_methods: Vec<Option<String>>,
+    /// Number of cache files with this content. Paths with different extensions have separate
+    /// cache files.
+    pub cache_files: usize,
+    /// Bytes saved by paths rechecked as links instead of copies
+    pub saved_bytes: u64,
+    /// Bytes that would be saved by rechecking the copies as links and storing the content in a
+    /// single cache file
+    pub potential_savings: u64,
+}
+
+#[pymethods]
+impl DuplicateGroup {
+    fn __len__(&self) -> usize {
+        self.paths.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateGroup(digest={:?}, size={}, paths={:?})",
+            self.digest, self.size, self.paths
+        )
+    }
+}
+
+/// Result of `XvcFile.duplicates`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateReport {
+    /// Groups of paths with identical content, largest potential savings first
+    pub groups: Vec<DuplicateGroup>,
+    /// Total bytes saved by links
+    pub saved_bytes: u64,
+    /// Total bytes that could be saved
+    pub potential_savings: u64,
+}
+
+#[pymethods]
+impl DuplicateReport {
+    fn __len__(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn __getitem__(&self, index: usize) -> PyResult<DuplicateGroup> {
+        self.groups.get(index).cloned().ok_or_else(|| {
+            pyo3::exceptions::PyIndexError::new_err("duplicate group index out of range")
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateReport(groups={}, saved_bytes={}, potential_savings={})",
+            self.groups.len(),
+            self.saved_bytes,
+            self.potential_savings
+        )
+    }
+
+    fn __str__(&self) -> String {
+        self.groups
+            .iter()
+            .flat_map(|g| {
+                std::iter::once(format!(
+                    "{} {} bytes, {} paths, {} bytes could be saved",
+                    &g.digest[..g.digest.len().min(12)],
+                    g.size,
+                    g.paths.len(),
+                    g.potential_savings
+                ))
+                .chain(
+                    g.paths
+                        .iter()
+                        .zip(&g.recheck_methods)
+                        .map(|(p, m)| format!("  {} ({})", p, m.as_deref().unwrap_or("-"))),
+                )
+            })
+            .chain(std::iter::once(format!(
+                "{} groups, {} bytes saved by links, {} bytes could be saved",
+                self.groups.len(),
+                self.saved_bytes,
+                self.potential_savings
+            )))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Name of `method` as used in options
+pub fn recheck_method_name(method: XvcRecheckMethod) -> &'static str {
+    match method {
+        XvcRecheckMethod::Copy => "copy",
+        XvcRecheckMethod::Hardlink => "hardlink",
+        XvcRecheckMethod::Symlink => "symlink",
+        XvcRecheckMethod::Reflink => "reflink",
+    }
+}
+
+/// Group `files` with the same digest. Files with unique content are left out.
+///
+/// Reflinks are counted as saving space, though they fall back to copies on file systems that
+/// don't support them.
+pub fn duplicates(files: Vec<TrackedFile>) -> DuplicateReport {
+    let mut by_digest = BTreeMap::<String, Vec<TrackedFile>>::new();
+    for file in files {
+        by_digest
+            .entry(file.digest.digest().hex_str())
+            .or_default()
+            .push(file);
+    }
+
+    let mut report = DuplicateReport::default();
+    for (digest, files) in by_digest.into_iter().filter(|(_, f)| f.len() > 1) {
+        let size = files.iter().find_map(|f| f.size).unwrap_or_default();
+        let methods: Vec<Option<&'static str>> = files
+            .iter()
+            .map(|f| f.recheck_method.map(recheck_method_name))
+            .collect();
+        let copies = methods.iter().filter(|m| **m == Some("copy")).count() as u64;
+        let links = methods
+            .iter()
+            .filter(|m| m.is_some() && **m != Some("copy"))
+            .count() as u64;
+        let cache_files = files
+            .iter()
+            .map(|f| f.cache_path.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+
+        let group = DuplicateGroup {
+            digest,
+            size,
+            paths: files.iter().map(|f| f.path.clone()).collect(),
+            recheck_methods: methods.iter().map(|m| m.map(str::to_string)).collect(),
+            cache_files,
+            saved_bytes: links * size,
+            potential_savings: (copies + cache_files as u64 - 1) * size,
+        };
+        report.saved_bytes += group.saved_bytes;
+        report.potential_savings += group.potential_savings;
+        report.groups.push(group);
+    }
+    report
+        .groups
+        .sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
+    report
+}
@@ -0,0 +1,202 @@
+//! Structured results of `file hash` and hashing of in-memory data.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyIterator, PyList, PyString};
+use tempfile::NamedTempFile;
+use xvc_config::FromConfigKey;
+use xvc_rust::core::{
+    ContentDigest, Error as XvcCoreError, HashAlgorithm as XvcHashAlgorithm,
+    TextOrBinary as XvcTextOrBinary, XvcRoot,
+};
+
+use super::data::for_each_chunk;
+use crate::XvcPyError;
+
+/// A content digest
+///
+/// Digests compare equal to other digests and to their lowercase hex strings, and hash like
+/// their hex strings.
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Digest {
+    /// Lowercase hex representation of the digest
+    #[pyo3(get)]
+    pub hex: String,
+    /// The algorithm used
+    #[pyo3(get)]
+    pub algorithm: String,
+}
+
+impl Digest {
+    fn raw(&self) -> Vec<u8> {
+        (0..self.hex.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(self.hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+}
+
+#[pymethods]
+impl Digest {
+    /// Raw bytes of the digest
+    #[getter]
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.raw())
+    }
+
+    fn __str__(&self) -> String {
+        self.hex.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Digest({:?}, algorithm={:?})", self.hex, self.algorithm)
+    }
+
+    fn __hash__(&self, py: Python) -> PyResult<isize> {
+        PyString::new(py, &self.hex).hash()
+    }
+
+    fn __eq__(&self, other: &Bound<PyAny>) -> bool {
+        if let Ok(other) = other.extract::<Digest>() {
+            self.hex == other.hex
+        } else if let Ok(other) = other.downcast::<PyString>() {
+            other
+                .extract::<String>()
+                .map(|o| o == self.hex)
+                .unwrap_or(false)
+        } else {
+            false
+        }
+    }
+}
+
+impl From<&ContentDigest> for Digest {
+    fn from(content_digest: &ContentDigest) -> Self {
+        let digest = content_digest.digest();
+        Digest {
+            hex: digest.hex_str().to_lowercase(),
+            algorithm: digest.algorithm.to_string(),
+        }
+    }
+}
+
+/// Result of `XvcFile.hash`
+///
+/// A read-only mapping of paths to [Digest] objects. `str()` gives the text output of the
+/// command.
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct HashResult {
+    pub digests: Vec<(String, Digest)>,
+}
+
+impl HashResult {
+    fn find(&self, path: &Bound<PyAny>) -> PyResult<Option<&Digest>> {
+        let path = path.extract::<PathBuf>()?.to_string_lossy().to_string();
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        Ok(self.digests.iter().find(|(p, _)| p == path).map(|(_, d)| d))
+    }
+}
+
+#[pymethods]
+impl HashResult {
+    fn __len__(&self) -> usize {
+        self.digests.len()
+    }
+
+    fn __getitem__(&self, path: &Bound<PyAny>) -> PyResult<Digest> {
+        match self.find(path)? {
+            Some(digest) => Ok(digest.clone()),
+            None => Err(PyKeyError::new_err(path.clone().unbind())),
+        }
+    }
+
+    fn __contains__(&self, path: &Bound<PyAny>) -> PyResult<bool> {
+        Ok(self.find(path)?.is_some())
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        self.keys(py)?.try_iter()
+    }
+
+    #[pyo3(signature = (path, default=None))]
+    fn get(&self, path: &Bound<PyAny>, default: Option<Digest>) -> PyResult<Option<Digest>> {
+        Ok(self.find(path)?.cloned().or(default))
+    }
+
+    fn keys<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        PyList::new(py, self.digests.iter().map(|(p, _)| p))
+    }
+
+    fn values<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        PyList::new(py, self.digests.iter().map(|(_, d)| d.clone()))
+    }
+
+    fn items<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        PyList::new(py, self.digests.iter().cloned())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("HashResult(paths={})", self.digests.len())
+    }
+
+    fn __str__(&self) -> String {
+        self.digests
+            .iter()
+            .map(|(p, d)| format!("{}\t{}", d.hex, p))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Parse the output of `file hash`, a digest and a path separated by a tab in each line
+pub fn parse_digests(output: &str, algorithm: &str) -> HashResult {
+    let digests = output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(digest, _)| digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|(digest, path)| {
+            (
+                path.to_string(),
+                Digest {
+                    hex: digest.to_lowercase(),
+                    algorithm: algorithm.to_string(),
+                },
+            )
+        })
+        .collect();
+    HashResult { digests }
+}
+
+/// The hash algorithm set in the configuration of the repository
+pub fn configured_algorithm(xvc_root: &XvcRoot) -> XvcHashAlgorithm {
+    XvcHashAlgorithm::from_conf(xvc_root.config())
+}
+
+/// Calculate the content digest of `data` with the rules Xvc uses for files.
+///
+/// The data is written to a temporary file and hashed with the same function `file track` and
+/// `file hash` use, so line ending handling for text content is identical. Without `algorithm`,
+/// the algorithm in the repository configuration is used.
+pub fn hash_data(
+    py: Python,
+    xvc_root: &XvcRoot,
+    data: &Bound<PyAny>,
+    algorithm: Option<XvcHashAlgorithm>,
+    text_or_binary: Option<XvcTextOrBinary>,
+) -> PyResult<Digest> {
+    let algorithm = algorithm.unwrap_or_else(|| configured_algorithm(xvc_root));
+    let text_or_binary = text_or_binary.unwrap_or(XvcTextOrBinary::Auto);
+
+    let mut file = NamedTempFile::new()?;
+    for_each_chunk(data, |chunk| Ok(file.write_all(chunk)?))?;
+    file.flush()?;
+    let digest = py
+        .allow_threads(|| ContentDigest::new(file.path(), algorithm, text_or_binary))
+        .map_err(|e: XvcCoreError| XvcPyError(e.into()))?;
+    Ok(Digest::from(&digest))
+}
@@ -0,0 +1,235 @@
+//! Versions of tracked files in the Git history of the Xvc stores.
+
+use std::collections::HashSet;
+
+use pyo3::prelude::*;
+use xvc_rust::core::{ContentDigest, XvcPath, XvcRoot};
+
+use super::hash::Digest;
+use super::store::{self, CommittedStores, Records};
+
+const FIELD_SEPARATOR: char = '\x1f';
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// A recorded version of a tracked file
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct FileVersion {
+    pub path: String,
+    pub digest: Digest,
+    /// Size of the file in bytes
+    pub size: Option<u64>,
+    /// SHA of the commit that recorded this version
+    pub commit: String,
+    /// Commit author as `Name <email>`
+    pub author: String,
+    /// Commit date in ISO 8601 format
+    pub date: String,
+    /// Subject of the commit message
+    pub message: String,
+}
+
+#[pymethods]
+impl FileVersion {
+    fn __repr__(&self) -> String {
+        format!(
+            "FileVersion(path={:?}, digest={:?}, commit={:?}, date={:?})",
+            self.path,
+            self.digest.hex,
+            &self.commit[..self.commit.len().min(8)],
+            self.date
+        )
+    }
+}
+
+/// Comparison of a tracked file between two versions
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct FileDiff {
+    pub path: String,
+    pub ref_a: String,
+    /// `None` when compared with the records in the workspace
+    pub ref_b: Option<String>,
+    pub digest_a: Option<Digest>,
+    pub digest_b: Option<Digest>,
+    pub size_a: Option<u64>,
+    pub size_b: Option<u64>,
+    /// One of `added`, `removed`, `modified`, `unchanged` or `untracked`
+    pub status: String,
+}
+
+#[pymethods]
+impl FileDiff {
+    /// Whether the content differs between the versions
+    #[getter]
+    fn changed(&self) -> bool {
+        self.digest_a != self.digest_b
+    }
+
+    fn __bool__(&self) -> bool {
+        self.changed()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FileDiff(path={:?}, ref_a={:?}, ref_b={:?}, status={:?})",
+            self.path, self.ref_a, self.ref_b, self.status
+        )
+    }
+}
+
+struct Commit {
+    sha: String,
+    author: String,
+    date: String,
+    message: String,
+}
+
+/// Commits that changed the record stores, oldest first
+fn store_commits(xvc_root: &XvcRoot) -> PyResult<Vec<Commit>> {
+    let stores = store::record_stores(xvc_root);
+    let mut args = vec![
+        "log",
+        "--reverse",
+        "--format=%H%x1f%an <%ae>%x1f%aI%x1f%s%x1e",
+        "--",
+    ];
+    args.extend(stores.iter().map(|s| s.as_str()));
+    let output = store::git(xvc_root, &args)?;
+    let output = String::from_utf8_lossy(&output);
+
+    Ok(output
+        .split(RECORD_SEPARATOR)
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.trim().split(FIELD_SEPARATOR).collect();
+            match fields.as_slice() {
+                [sha, author, date, message] => Some(Commit {
+                    sha: sha.to_string(),
+                    author: author.to_string(),
+                    date: date.to_string(),
+                    message: message.to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+/// Versions of `xvc_path` recorded in the Git history, newest first.
+///
+/// Consecutive commits recording the same digest are listed once, with the first of them. A
+/// digest recorded again after a different one is listed again.
+pub fn history(xvc_root: &XvcRoot, xvc_path: &XvcPath) -> PyResult<Vec<FileVersion>> {
+    // Repositories without commits have no history
+    if store::resolve_ref(xvc_root, "HEAD").is_err() {
+        return Ok(Vec::new());
+    }
+    let commits = store_commits(xvc_root)?;
+    let shas: Vec<&str> = commits.iter().map(|c| c.sha.as_str()).collect();
+    let stores = CommittedStores::read(xvc_root, &shas)?;
+    let mut previous: Option<String> = None;
+    let mut versions = Vec::<FileVersion>::new();
+    for (index, commit) in commits.into_iter().enumerate() {
+        let records = stores.records(index)?;
+        let Some(digest) = records.digest(xvc_path) else {
+            previous = None;
+            continue;
+        };
+        let digest = Digest::from(&digest);
+        if previous.as_ref() == Some(&digest.hex) {
+            continue;
+        }
+        previous = Some(digest.hex.clone());
+        versions.push(FileVersion {
+            path: xvc_path.to_string(),
+            digest,
+            size: records.size(xvc_path),
+            commit: commit.sha,
+            author: commit.author,
+            date: commit.date,
+            message: commit.message,
+        });
+    }
+    versions.reverse();
+    Ok(versions)
+}
+
+/// A digest recorded for a path in the Git history or the workspace
+#[derive(Clone, Debug)]
+pub struct RecordedVersion {
+    pub xvc_path: XvcPath,
+    pub digest: ContentDigest,
+    pub size: Option<u64>,
+}
+
+/// Distinct digests of `xvc_paths` recorded in the Git history and in the workspace, oldest
+/// first.
+///
+/// Unlike [history], the stores of each commit are loaded once for all paths.
+pub fn recorded_versions(
+    xvc_root: &XvcRoot,
+    xvc_paths: &[XvcPath],
+) -> PyResult<Vec<RecordedVersion>> {
+    // Repositories without commits have only the records in the workspace
+    let commits = match store::resolve_ref(xvc_root, "HEAD") {
+        Ok(_) => store_commits(xvc_root)?,
+        Err(_) => Vec::new(),
+    };
+    let shas: Vec<&str> = commits.iter().map(|c| c.sha.as_str()).collect();
+    let stores = CommittedStores::read(xvc_root, &shas)?;
+    let mut seen = HashSet::new();
+    let mut versions = Vec::<RecordedVersion>::new();
+    let commit_records = (0..shas.len()).map(|index| stores.records(index));
+    for records in commit_records.chain(std::iter::once(Records::load(xvc_root, None))) {
+        let records = records?;
+        for xvc_path in xvc_paths {
+            let Some(digest) = records.digest(xvc_path) else {
+                continue;
+            };
+            if !seen.insert((xvc_path.to_string(), digest.digest().hex_str())) {
+                continue;
+            }
+            versions.push(RecordedVersion {
+                xvc_path: xvc_path.clone(),
+                digest,
+                size: records.size(xvc_path),
+            });
+        }
+    }
+    Ok(versions)
+}
+
+/// Compare the records of `xvc_path` at `ref_a` and `ref_b`, or the workspace if `ref_b` is
+/// `None`
+pub fn diff(
+    xvc_root: &XvcRoot,
+    xvc_path: &XvcPath,
+    ref_a: &str,
+    ref_b: Option<&str>,
+) -> PyResult<FileDiff> {
+    let records_a = Records::load(xvc_root, Some(&store::resolve_ref(xvc_root, ref_a)?))?;
+    let records_b = match ref_b {
+        Some(r) => Records::load(xvc_root, Some(&store::resolve_ref(xvc_root, r)?))?,
+        None => Records::load(xvc_root, None)?,
+    };
+    let digest_a = records_a.digest(xvc_path).as_ref().map(Digest::from);
+    let digest_b = records_b.digest(xvc_path).as_ref().map(Digest::from);
+    let status = match (&digest_a, &digest_b) {
+        (None, None) => "untracked",
+        (None, Some(_)) => "added",
+        (Some(_), None) => "removed",
+        (Some(a), Some(b)) if a == b => "unchanged",
+        (Some(_), Some(_)) => "modified",
+    };
+
+    Ok(FileDiff {
+        path: xvc_path.to_string(),
+        ref_a: ref_a.to_string(),
+        ref_b: ref_b.map(str::to_string),
+        digest_a,
+        digest_b,
+        size_a: records_a.size(xvc_path),
+        size_b: records_b.size(xvc_path),
+        status: status.to_string(),
+    })
+}
@@ -0,0 +1,108 @@
+//! Key-value labels of tracked files.
+//!
+//! Labels are stored as a component of the file entities in their own store, so they are
+//! committed to Git and versioned with the other records.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+use xvc_rust::core::{XvcPath, XvcRoot};
+use xvc_rust::ecs::{Storable, XvcStore};
+
+use super::list::FileEntry;
+use super::store::Records;
+use crate::XvcPyError;
+
+/// Labels of a tracked file
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FileLabels(pub BTreeMap<String, String>);
+
+impl Storable for FileLabels {
+    fn type_description() -> String {
+        "file-labels".to_string()
+    }
+}
+
+fn load(xvc_root: &XvcRoot) -> PyResult<XvcStore<FileLabels>> {
+    XvcStore::load_store(xvc_root.store_dir()).map_err(|e| XvcPyError(e.into()).into())
+}
+
+/// Convert label values given as keyword arguments to strings. `None` values are kept as `None`
+/// to remove the label.
+pub fn label_values(labels: Option<&Bound<PyDict>>) -> PyResult<BTreeMap<String, Option<String>>> {
+    let mut values = BTreeMap::new();
+    if let Some(labels) = labels {
+        for (key, value) in labels.iter() {
+            let value = if value.is_none() {
+                None
+            } else {
+                Some(value.str()?.to_string())
+            };
+            values.insert(key.extract::<String>()?, value);
+        }
+    }
+    Ok(values)
+}
+
+/// Set `labels` of the tracked `xvc_path`, removing those with `None` values, and return all
+/// labels of the path
+pub fn annotate(
+    xvc_root: &XvcRoot,
+    xvc_path: &XvcPath,
+    labels: BTreeMap<String, Option<String>>,
+) -> PyResult<BTreeMap<String, String>> {
+    let entity = Records::load(xvc_root, None)?
+        .entity(xvc_path)
+        .ok_or_else(|| PyKeyError::new_err(format!("'{}' is not tracked", xvc_path)))?;
+    let mut store = load(xvc_root)?;
+    let mut current = store.get(&entity).cloned().unwrap_or_default();
+    for (key, value) in labels {
+        match value {
+            Some(value) => current.0.insert(key, value),
+            None => current.0.remove(&key),
+        };
+    }
+    store.insert(entity, current.clone());
+    store
+        .save(xvc_root.store_dir())
+        .map_err(|e| XvcPyError(e.into()))?;
+    Ok(current.0)
+}
+
+/// Labels of tracked paths, by path
+pub fn labels_by_path(xvc_root: &XvcRoot) -> PyResult<HashMap<String, BTreeMap<String, String>>> {
+    let records = Records::load(xvc_root, None)?;
+    let store = load(xvc_root)?;
+    Ok(records
+        .paths
+        .iter()
+        .filter_map(|(entity, xvc_path)| {
+            store
+                .get(entity)
+                .map(|labels| (xvc_path.to_string(), labels.0.clone()))
+        })
+        .collect())
+}
+
+/// Set the labels of `entries` and keep only those with all of the `filter` labels. A `None`
+/// value in `filter` matches entries without the label.
+pub fn apply(
+    xvc_root: &XvcRoot,
+    entries: Vec<FileEntry>,
+    filter: &BTreeMap<String, Option<String>>,
+) -> PyResult<Vec<FileEntry>> {
+    let mut labels = labels_by_path(xvc_root)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.labels = labels.remove(&entry.path).unwrap_or_default();
+            filter
+                .iter()
+                .all(|(key, value)| entry.labels.get(key) == value.as_ref())
+                .then_some(entry)
+        })
+        .collect())
+}
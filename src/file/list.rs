@@ -0,0 +1,345 @@
+//! Structured results of `file list`.
+//!
+//! The command is run with [ENTRY_FORMAT], which puts each field of an entry in a separate
+//! column, and the output is parsed to [FileEntry] objects. Lookups that don't need the content
+//! digests of the workspace files use [LOOKUP_FORMAT] to avoid hashing them.
+
+use std::collections::BTreeMap;
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyIterator, PyList};
+
+use crate::table;
+
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// The list format used to parse entries, fields are separated by [FIELD_SEPARATOR]
+pub const ENTRY_FORMAT: &str = "{{aft}}\x1f{{rrm}}\x1f{{asz}}\x1f{{rsz}}\x1f{{ats}}\x1f{{rts}}\x1f{{rcd64}}\x1f{{acd64}}\x1f{{cst}}\x1f{{name}}";
+
+/// [ENTRY_FORMAT] without the digests of the workspace files, which are left empty
+pub const LOOKUP_FORMAT: &str = "{{aft}}\x1f{{rrm}}\x1f{{asz}}\x1f{{rsz}}\x1f{{ats}}\x1f{{rts}}\x1f{{rcd64}}\x1f\x1f{{cst}}\x1f{{name}}";
+
+/// A file or directory in the workspace or in the Xvc stores
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    #[pyo3(get)]
+    pub path: String,
+    /// One of `file`, `directory`, `symlink`, `hardlink`, `reflink` or `missing`
+    #[pyo3(get)]
+    pub file_type: String,
+    /// Size of the workspace file in bytes
+    #[pyo3(get)]
+    pub size: Option<u64>,
+    /// Size of the recorded version in bytes
+    #[pyo3(get)]
+    pub recorded_size: Option<u64>,
+    /// Modification time of the workspace file, as `YYYY-MM-DD HH:MM:SS`
+    #[pyo3(get)]
+    pub mtime: Option<String>,
+    /// Modification time of the recorded version, as `YYYY-MM-DD HH:MM:SS`
+    #[pyo3(get)]
+    pub recorded_mtime: Option<String>,
+    #[pyo3(get)]
+    pub recorded_digest: Option<String>,
+    /// `None` when the entries are listed without workspace digests
+    #[pyo3(get)]
+    pub actual_digest: Option<String>,
+    /// `None` for untracked paths
+    #[pyo3(get)]
+    pub recheck_method: Option<String>,
+    /// One of `current`, `newer`, `older`, `missing` or `untracked`
+    #[pyo3(get)]
+    pub cache_status: String,
+    /// Whether the workspace content differs from the recorded content
+    #[pyo3(get)]
+    pub changed: bool,
+    /// Labels set with `XvcFile.annotate`
+    #[pyo3(get)]
+    pub labels: BTreeMap<String, String>,
+    /// Raw fields for rendering the entry with a list format
+    aft: String,
+    rrm: String,
+    cst: String,
+}
+
+#[pymethods]
+impl FileEntry {
+    fn __repr__(&self) -> String {
+        format!(
+            "FileEntry(path={:?}, file_type={:?}, size={:?}, recheck_method={:?}, changed={})",
+            self.path,
+            self.file_type,
+            self.size,
+            self.recheck_method,
+            if self.changed { "True" } else { "False" }
+        )
+    }
+}
+
+/// Totals of a file list
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct FileListSummary {
+    pub total: usize,
+    /// Total size of the workspace files in bytes
+    pub workspace_size: u64,
+    /// Total recorded size of the tracked files in bytes
+    pub cached_size: u64,
+}
+
+#[pymethods]
+impl FileListSummary {
+    fn __repr__(&self) -> String {
+        format!(
+            "FileListSummary(total={}, workspace_size={}, cached_size={})",
+            self.total, self.workspace_size, self.cached_size
+        )
+    }
+
+    fn __str__(&self) -> String {
+        format!(
+            "Total #: {} Workspace Size: {} Cached Size: {}",
+            self.total, self.workspace_size, self.cached_size
+        )
+    }
+}
+
+/// Result of `XvcFile.list`
+///
+/// A sequence of [FileEntry] objects. `str()` renders the entries with the list format given to
+/// the command.
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct FileList {
+    pub entries: Vec<FileEntry>,
+    #[pyo3(get)]
+    pub summary: FileListSummary,
+    template: String,
+    show_summary: bool,
+}
+
+impl FileList {
+    pub fn new(entries: Vec<FileEntry>, template: String, show_summary: bool) -> Self {
+        let summary = FileListSummary {
+            total: entries.len(),
+            workspace_size: entries
+                .iter()
+                .filter(|e| e.file_type != "directory")
+                .filter_map(|e| e.size)
+                .sum(),
+            cached_size: entries
+                .iter()
+                .filter(|e| e.recorded_digest.is_some())
+                .filter_map(|e| e.recorded_size)
+                .sum(),
+        };
+        Self {
+            entries,
+            summary,
+            template,
+            show_summary,
+        }
+    }
+
+    fn columns<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let columns = PyDict::new(py);
+        macro_rules! column {
+            ($name:literal, $field:ident) => {
+                columns.set_item(
+                    $name,
+                    self.entries
+                        .iter()
+                        .map(|e| e.$field.clone())
+                        .collect::<Vec<_>>(),
+                )?;
+            };
+        }
+        column!("path", path);
+        column!("file_type", file_type);
+        column!("size", size);
+        column!("recorded_size", recorded_size);
+        column!("mtime", mtime);
+        column!("recorded_mtime", recorded_mtime);
+        column!("recorded_digest", recorded_digest);
+        column!("actual_digest", actual_digest);
+        column!("recheck_method", recheck_method);
+        column!("cache_status", cache_status);
+        column!("changed", changed);
+        column!("labels", labels);
+        Ok(columns)
+    }
+}
+
+#[pymethods]
+impl FileList {
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<FileEntry> {
+        let len = self.entries.len() as isize;
+        let i = if index < 0 { len + index } else { index };
+        if i < 0 || i >= len {
+            return Err(pyo3::exceptions::PyIndexError::new_err(
+                "file list index out of range",
+            ));
+        }
+        Ok(self.entries[i as usize].clone())
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.entries.clone())?.try_iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FileList(entries={})", self.entries.len())
+    }
+
+    fn __str__(&self) -> String {
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|e| render(&self.template, e))
+            .collect();
+        if self.show_summary {
+            lines.push(FileListSummary::__str__(&self.summary));
+        }
+        lines.join("\n")
+    }
+
+    fn to_pandas<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        table::to_pandas(&self.columns(py)?)
+    }
+
+    fn to_polars<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        table::to_polars(&self.columns(py)?)
+    }
+
+    fn to_arrow<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        table::to_arrow(&self.columns(py)?)
+    }
+}
+
+/// Fields printed as dashes or spaces are missing
+fn non_empty(field: &str) -> Option<String> {
+    let field = field.trim();
+    if field.is_empty() || field.chars().all(|c| c == '-') {
+        None
+    } else {
+        Some(field.to_string())
+    }
+}
+
+fn file_type(aft: &str) -> &'static str {
+    match aft {
+        "F" => "file",
+        "D" => "directory",
+        "S" => "symlink",
+        "H" => "hardlink",
+        "R" => "reflink",
+        _ => "missing",
+    }
+}
+
+fn recheck_method(rrm: &str) -> Option<String> {
+    match rrm {
+        "C" => Some("copy".to_string()),
+        "H" => Some("hardlink".to_string()),
+        "S" => Some("symlink".to_string()),
+        "R" => Some("reflink".to_string()),
+        _ => None,
+    }
+}
+
+fn cache_status(cst: &str) -> String {
+    match cst {
+        "=" => "current",
+        ">" => "newer",
+        "<" => "older",
+        "X" => "missing",
+        "?" => "untracked",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Parse a line printed with [ENTRY_FORMAT] or [LOOKUP_FORMAT]. `changed` is only set with
+/// `actual_digests`.
+fn parse_entry(line: &str, actual_digests: bool) -> Option<FileEntry> {
+    let fields: Vec<&str> = line.split(FIELD_SEPARATOR).collect();
+    let [aft, rrm, asz, rsz, ats, rts, rcd, acd, cst, name] = fields.as_slice() else {
+        return None;
+    };
+    let (aft, rrm, cst) = (aft.trim(), rrm.trim(), cst.trim());
+    let recorded_digest = non_empty(rcd);
+    let actual_digest = non_empty(acd);
+    let changed = actual_digests && recorded_digest.is_some() && recorded_digest != actual_digest;
+
+    Some(FileEntry {
+        path: name.trim().to_string(),
+        file_type: file_type(aft).to_string(),
+        size: non_empty(asz).and_then(|s| s.parse().ok()),
+        recorded_size: non_empty(rsz).and_then(|s| s.parse().ok()),
+        mtime: non_empty(ats),
+        recorded_mtime: non_empty(rts),
+        recorded_digest,
+        actual_digest,
+        recheck_method: recheck_method(rrm),
+        cache_status: cache_status(cst),
+        changed,
+        labels: BTreeMap::new(),
+        aft: aft.to_string(),
+        rrm: rrm.to_string(),
+        cst: cst.to_string(),
+    })
+}
+
+/// Parse the output of `file list` run with `--no-summary` and [ENTRY_FORMAT], or
+/// [LOOKUP_FORMAT] without `actual_digests`
+pub fn parse_entries(output: &str, actual_digests: bool) -> Vec<FileEntry> {
+    output
+        .lines()
+        .filter_map(|line| parse_entry(line, actual_digests))
+        .collect()
+}
+
+/// Render an entry with a list format template
+fn render(template: &str, entry: &FileEntry) -> String {
+    let digest = |d: &Option<String>, len: usize| match d {
+        Some(d) => d.chars().take(len).collect(),
+        None => " ".repeat(len),
+    };
+    let optional = |v: &Option<String>| v.clone().unwrap_or_default();
+    template
+        .replace("{{aft}}", &entry.aft)
+        .replace("{{rrm}}", &entry.rrm)
+        .replace(
+            "{{asz}}",
+            &format!(
+                "{:>12}",
+                entry.size.map(|s| s.to_string()).unwrap_or_default()
+            ),
+        )
+        .replace(
+            "{{rsz}}",
+            &format!(
+                "{:>12}",
+                entry
+                    .recorded_size
+                    .map(|s| s.to_string())
+                    .unwrap_or_default()
+            ),
+        )
+        .replace("{{ats}}", &format!("{:>19}", optional(&entry.mtime)))
+        .replace(
+            "{{rts}}",
+            &format!("{:>19}", optional(&entry.recorded_mtime)),
+        )
+        .replace("{{rcd8}}", &digest(&entry.recorded_digest, 8))
+        .replace("{{rcd64}}", &digest(&entry.recorded_digest, 64))
+        .replace("{{acd8}}", &digest(&entry.actual_digest, 8))
+        .replace("{{acd64}}", &digest(&entry.actual_digest, 64))
+        .replace("{{cst}}", &entry.cst)
+        .replace("{{name}}", &entry.path)
+}
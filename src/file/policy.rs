@@ -0,0 +1,262 @@
+//! Recheck methods chosen by path patterns.
+//!
+//! Rules are kept in `.xvc/recheck-policy.json` and committed with the other Xvc files. A path
+//! uses the recheck method of the last rule it matches, like later lines of `.gitignore` override
+//! earlier ones. Patterns are matched with `fnmatch` against paths relative to the repository
+//! root, so `*` also matches `/`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use serde::{Deserialize, Serialize};
+use xvc_rust::core::{RecheckMethod as XvcRecheckMethod, XvcRoot};
+use xvc_rust::file::{recheck::RecheckCLI, XvcFileSubCommand};
+
+use super::duplicates::recheck_method_name;
+use super::store::{self, TrackedFile};
+use super::{command_string, XvcFile};
+use crate::{get_targets, parse_value, to_cli_values};
+
+const POLICY_FILE: &str = "recheck-policy.json";
+
+/// A pattern and the recheck method of paths matching it
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub pattern: String,
+    pub recheck_method: String,
+}
+
+#[pymethods]
+impl PolicyRule {
+    fn __repr__(&self) -> String {
+        format!(
+            "PolicyRule(pattern={:?}, recheck_method={:?})",
+            self.pattern, self.recheck_method
+        )
+    }
+}
+
+/// A tracked file whose recorded recheck method differs from the policy
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct PolicyDeviation {
+    pub path: String,
+    /// The recorded recheck method
+    pub recheck_method: Option<String>,
+    /// The recheck method of the matching rule
+    pub expected: String,
+    /// Pattern of the matching rule
+    pub pattern: String,
+}
+
+#[pymethods]
+impl PolicyDeviation {
+    fn __repr__(&self) -> String {
+        format!(
+            "PolicyDeviation(path={:?}, recheck_method={:?}, expected={:?})",
+            self.path, self.recheck_method, self.expected
+        )
+    }
+}
+
+/// The rules of a repository
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Policy {
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    fn path(xvc_root: &XvcRoot) -> PathBuf {
+        xvc_root.xvc_dir().join(POLICY_FILE)
+    }
+
+    pub fn load(xvc_root: &XvcRoot) -> PyResult<Self> {
+        let path = Self::path(xvc_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+            PyValueError::new_err(format!("invalid {}: {}", path.to_string_lossy(), e))
+        })
+    }
+
+    fn save(&self, xvc_root: &XvcRoot) -> PyResult<()> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        fs::write(Self::path(xvc_root), content + "\n")?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The last rule matching `path`
+    pub fn rule_for(&self, py: Python, path: &str) -> PyResult<Option<&PolicyRule>> {
+        let fnmatch = py.import("fnmatch")?;
+        for rule in self.rules.iter().rev() {
+            if fnmatch
+                .call_method1("fnmatchcase", (path, &rule.pattern))?
+                .is_truthy()?
+            {
+                return Ok(Some(rule));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tracked files among `files` whose recheck method differs from their rule
+    pub fn deviations(&self, py: Python, files: &[TrackedFile]) -> PyResult<Vec<PolicyDeviation>> {
+        let mut deviations = Vec::new();
+        for file in files {
+            let Some(rule) = self.rule_for(py, &file.path)? else {
+                continue;
+            };
+            let recheck_method = file.recheck_method.map(recheck_method_name);
+            if recheck_method != Some(rule.recheck_method.as_str()) {
+                deviations.push(PolicyDeviation {
+                    path: file.path.clone(),
+                    recheck_method: recheck_method.map(str::to_string),
+                    expected: rule.recheck_method.clone(),
+                    pattern: rule.pattern.clone(),
+                });
+            }
+        }
+        Ok(deviations)
+    }
+}
+
+/// Recheck the deviating files in `targets` with the methods of their rules, without Git
+/// automation. Returns the outputs of the commands.
+pub fn apply(py: Python, xvc_file: &XvcFile, targets: Vec<String>) -> PyResult<String> {
+    let xvc_root = store::xvc_root(&xvc_file.xvc_opts)?;
+    let policy = Policy::load(&xvc_root)?;
+    if policy.is_empty() {
+        return Ok(String::new());
+    }
+    let (_, files) = xvc_file.tracked_files(targets)?;
+
+    let mut by_method = BTreeMap::<String, Vec<String>>::new();
+    for deviation in policy.deviations(py, &files)? {
+        by_method
+            .entry(deviation.expected)
+            .or_default()
+            .push(deviation.path);
+    }
+    let mut output = String::new();
+    for (method, paths) in by_method {
+        let recheck_cli = RecheckCLI {
+            recheck_method: Some(parse_value(&method, "recheck_method")?),
+            force: false,
+            no_parallel: false,
+            targets: Some(paths.clone()),
+        };
+        let command = command_string("recheck", &paths);
+        output
+            .push_str(&xvc_file.run_without_git(XvcFileSubCommand::Recheck(recheck_cli), command)?);
+    }
+    Ok(output)
+}
+
+/// Recheck method policy of a repository, returned by `XvcFile.policy`
+#[pyclass(module = "xvc")]
+pub struct RecheckPolicy {
+    xvc_file: XvcFile,
+}
+
+impl RecheckPolicy {
+    pub fn new(xvc_file: XvcFile) -> Self {
+        Self { xvc_file }
+    }
+
+    fn xvc_root(&self) -> PyResult<XvcRoot> {
+        store::xvc_root(&self.xvc_file.xvc_opts)
+    }
+
+    fn update(&self, command: String, f: impl FnOnce(&mut Policy) -> PyResult<()>) -> PyResult<()> {
+        let xvc_root = self.xvc_root()?;
+        let mut policy = Policy::load(&xvc_root)?;
+        f(&mut policy)?;
+        policy.save(&xvc_root)?;
+        self.xvc_file.commit(command)
+    }
+}
+
+#[pymethods]
+impl RecheckPolicy {
+    /// The rules, in the order they are applied
+    fn rules(&self) -> PyResult<Vec<PolicyRule>> {
+        Ok(Policy::load(&self.xvc_root()?)?.rules)
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.rules()?.len())
+    }
+
+    /// Add a rule checking out paths matching `pattern` with `recheck_method`. A rule with the
+    /// same pattern is replaced.
+    fn add(&self, pattern: String, recheck_method: &Bound<PyAny>) -> PyResult<()> {
+        let mut values = Vec::new();
+        to_cli_values(recheck_method, "recheck_method", &mut values)?;
+        let [method] = values.as_slice() else {
+            return Err(PyTypeError::new_err(
+                "recheck_method expects a single value",
+            ));
+        };
+        let method: XvcRecheckMethod = parse_value(method, "recheck_method")?;
+        let rule = PolicyRule {
+            pattern,
+            recheck_method: recheck_method_name(method).to_string(),
+        };
+        let command = format!(
+            "xvc file policy add {} {}",
+            rule.pattern, rule.recheck_method
+        );
+        self.update(command, |policy| {
+            policy.rules.retain(|r| r.pattern != rule.pattern);
+            policy.rules.push(rule);
+            Ok(())
+        })
+    }
+
+    /// Remove the rule with `pattern`
+    fn remove(&self, pattern: String) -> PyResult<()> {
+        let command = format!("xvc file policy remove {}", pattern);
+        self.update(command, |policy| {
+            let len = policy.rules.len();
+            policy.rules.retain(|r| r.pattern != pattern);
+            if policy.rules.len() == len {
+                return Err(PyKeyError::new_err(format!("no rule for '{}'", pattern)));
+            }
+            Ok(())
+        })
+    }
+
+    /// Tracked files in `targets`, or in the whole workspace, whose recorded recheck method
+    /// differs from the policy
+    #[pyo3(signature = (*targets))]
+    fn deviations(&self, py: Python, targets: &Bound<PyTuple>) -> PyResult<Vec<PolicyDeviation>> {
+        let policy = Policy::load(&self.xvc_root()?)?;
+        let (_, files) = self.xvc_file.tracked_files(get_targets(targets)?)?;
+        policy.deviations(py, &files)
+    }
+
+    /// Recheck the deviating files in `targets`, or in the whole workspace, with the methods of
+    /// their rules
+    #[pyo3(signature = (*targets))]
+    fn apply(&self, py: Python, targets: &Bound<PyTuple>) -> PyResult<String> {
+        let targets = get_targets(targets)?;
+        let output = apply(py, &self.xvc_file, targets.clone())?;
+        self.xvc_file.commit(command_string("recheck", &targets))?;
+        Ok(output)
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("RecheckPolicy(rules={})", self.rules()?.len()))
+    }
+}
@@ -0,0 +1,94 @@
+//! Writing in-memory data to workspace files before tracking them.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyFileExistsError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use super::data::for_each_chunk;
+
+/// Methods of pandas and Polars data frames writing to a file, with their file extensions
+const DATAFRAME_WRITERS: &[(&str, &str)] = &[
+    ("parquet", "to_parquet"),
+    ("parquet", "write_parquet"),
+    ("csv", "to_csv"),
+    ("csv", "write_csv"),
+];
+
+/// Write a data frame with the writer method for the extension of `path`. Returns `false` if
+/// `data` isn't a data frame.
+fn write_dataframe(data: &Bound<PyAny>, path: &Path, target: &Path) -> PyResult<bool> {
+    let methods: Vec<&(&str, &str)> = DATAFRAME_WRITERS
+        .iter()
+        .filter(|(_, method)| data.hasattr(*method).unwrap_or(false))
+        .collect();
+    if methods.is_empty() {
+        return Ok(false);
+    }
+
+    let extension = target
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let Some((_, method)) = methods.iter().find(|(ext, _)| *ext == extension) else {
+        return Err(PyTypeError::new_err(format!(
+            "data frames can be written to .parquet or .csv files, not '{}'",
+            target.to_string_lossy()
+        )));
+    };
+
+    if *method == "to_csv" {
+        let kwargs = PyDict::new(data.py());
+        kwargs.set_item("index", false)?;
+        data.call_method(*method, (path,), Some(&kwargs))?;
+    } else {
+        data.call_method1(*method, (path,))?;
+    }
+    Ok(true)
+}
+
+fn write_to(data: &Bound<PyAny>, path: &Path, target: &Path) -> PyResult<()> {
+    if write_dataframe(data, path, target)? {
+        return Ok(());
+    }
+    let mut file = fs::File::create(path)?;
+    for_each_chunk(data, |chunk| Ok(file.write_all(chunk)?))?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Write `data` to `path` in the workspace.
+///
+/// The content is written to a temporary file next to `path` and renamed, so an existing file,
+/// which may be a link to the cache, is replaced and never written into. Existing files are
+/// only replaced when `force` is set.
+pub fn write(data: &Bound<PyAny>, path: &Path, force: bool) -> PyResult<()> {
+    let exists = path.symlink_metadata().is_ok();
+    if exists && !force {
+        return Err(PyFileExistsError::new_err(format!(
+            "'{}' exists, use force=True to replace it",
+            path.to_string_lossy()
+        )));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path: PathBuf = path.with_file_name(format!(".{}.xvc-put", file_name));
+    if let Err(e) = write_to(data, &temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    if exists {
+        fs::remove_file(path)?;
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! Plans and reports of `file remove`.
+//!
+//! The cache files and storage objects a removal deletes are determined from the recorded
+//! versions of the targets before the command runs. After it runs, they are checked again to
+//! report what was actually removed.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use xvc_rust::core::{XvcCachePath, XvcRoot};
+
+use super::history::recorded_versions;
+use super::store::{self, Records, TrackedFile};
+use crate::XvcPyError;
+
+/// A cache file or storage object of a tracked file version
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct RemovedFile {
+    pub path: String,
+    /// Hex digest of the version
+    pub digest: String,
+    /// `cache`, or the name of the storage
+    pub location: String,
+    /// Path of the content relative to the cache directory, also used in storages
+    pub cache_path: String,
+    /// Size of the content in bytes
+    pub size: Option<u64>,
+}
+
+#[pymethods]
+impl RemovedFile {
+    fn __repr__(&self) -> String {
+        format!(
+            "RemovedFile(path={:?}, digest={:?}, location={:?})",
+            self.path, self.digest, self.location
+        )
+    }
+}
+
+/// Result of `XvcFile.remove`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct RemoveReport {
+    /// Whether this is the plan of a dry run, with nothing removed
+    pub dry_run: bool,
+    /// Files removed, or that would be removed in a dry run
+    pub removed: Vec<RemovedFile>,
+    /// Total size of `removed` in bytes
+    pub reclaimed_bytes: u64,
+    /// Files kept because their content is used by other tracked paths, with the reason
+    pub skipped: Vec<(RemovedFile, String)>,
+    /// Files that couldn't be removed, with the error message
+    pub failures: Vec<(RemovedFile, String)>,
+}
+
+#[pymethods]
+impl RemoveReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "RemoveReport(dry_run={}, removed={}, reclaimed_bytes={}, skipped={}, failed={})",
+            if self.dry_run { "True" } else { "False" },
+            self.removed.len(),
+            self.reclaimed_bytes,
+            self.skipped.len(),
+            self.failures.len()
+        )
+    }
+
+    fn __str__(&self) -> String {
+        let verb = if self.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        self.removed
+            .iter()
+            .map(|f| {
+                format!(
+                    "{} {} {} from {}",
+                    verb,
+                    f.path,
+                    &f.digest[..f.digest.len().min(12)],
+                    f.location
+                )
+            })
+            .chain(
+                self.skipped
+                    .iter()
+                    .map(|(f, reason)| format!("skipped {} in {}: {}", f.path, f.location, reason)),
+            )
+            .chain(
+                self.failures.iter().map(|(f, message)| {
+                    format!("[ERROR] {} in {}: {}", f.path, f.location, message)
+                }),
+            )
+            .chain(std::iter::once(format!(
+                "{} {} files, {} bytes",
+                verb,
+                self.removed.len(),
+                self.reclaimed_bytes
+            )))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Which versions of the targets are removed
+pub enum VersionSelection {
+    /// The version recorded in the workspace
+    Current,
+    /// All versions recorded in the Git history and the workspace
+    All,
+    /// Versions whose digest starts with the prefix, written with or without dashes
+    Prefix(String),
+}
+
+/// The files a removal of `versions` of `targets` would delete from the cache and `storage`
+pub struct RemovePlan {
+    /// Files to remove with the absolute path of their content in the cache
+    pub candidates: Vec<(RemovedFile, PathBuf)>,
+    pub skipped: Vec<(RemovedFile, String)>,
+}
+
+/// Plan the removal of `versions` of `targets` from the cache if `from_cache` is set and from
+/// `from_storage`. Content used by tracked paths outside `targets` is skipped unless `force` is
+/// set.
+pub fn plan(
+    xvc_root: &XvcRoot,
+    targets: &[TrackedFile],
+    versions: VersionSelection,
+    from_cache: bool,
+    from_storage: Option<&str>,
+    force: bool,
+) -> PyResult<RemovePlan> {
+    let xvc_paths: Vec<_> = targets.iter().map(|f| f.xvc_path.clone()).collect();
+    let versions: Vec<_> = match versions {
+        VersionSelection::Current => targets
+            .iter()
+            .map(|f| (f.xvc_path.clone(), f.digest.clone(), f.size))
+            .collect(),
+        VersionSelection::All => recorded_versions(xvc_root, &xvc_paths)?
+            .into_iter()
+            .map(|v| (v.xvc_path, v.digest, v.size))
+            .collect(),
+        VersionSelection::Prefix(prefix) => {
+            let prefix = prefix.replace('-', "").to_lowercase();
+            recorded_versions(xvc_root, &xvc_paths)?
+                .into_iter()
+                .filter(|v| v.digest.digest().hex_str().starts_with(&prefix))
+                .map(|v| (v.xvc_path, v.digest, v.size))
+                .collect()
+        }
+    };
+
+    // Cache paths of tracked files that aren't removed, which keep their content
+    let target_paths: HashSet<String> = targets.iter().map(|f| f.path.clone()).collect();
+    let records = Records::load(xvc_root, None)?;
+    let mut used_by = HashMap::new();
+    for (entity, xvc_path) in records.paths.iter() {
+        let path = xvc_path.to_string();
+        if target_paths.contains(&path) {
+            continue;
+        }
+        if let Some(digest) = records.digests.get(entity) {
+            let cache_path =
+                XvcCachePath::new(xvc_path, digest).map_err(|e| XvcPyError(e.into()))?;
+            used_by.insert(cache_path.to_string(), path);
+        }
+    }
+
+    let storage_paths = match from_storage {
+        Some(storage) => Some(store::storage_paths(xvc_root, storage)?),
+        None => None,
+    };
+
+    let mut plan = RemovePlan {
+        candidates: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (xvc_path, digest, size) in versions {
+        let cache_path = XvcCachePath::new(&xvc_path, &digest).map_err(|e| XvcPyError(e.into()))?;
+        let cache_file = cache_path.to_absolute_path(xvc_root).to_path_buf();
+        let cache_path = cache_path.to_string();
+        let file = |location: &str, size: Option<u64>| RemovedFile {
+            path: xvc_path.to_string(),
+            digest: digest.digest().hex_str(),
+            location: location.to_string(),
+            cache_path: cache_path.clone(),
+            size,
+        };
+
+        let mut found = Vec::new();
+        if from_cache {
+            if let Ok(metadata) = cache_file.metadata() {
+                found.push(file("cache", Some(metadata.len())));
+            }
+        }
+        if let (Some(storage), Some(storage_paths)) = (from_storage, &storage_paths) {
+            if storage_paths.contains(&cache_path) {
+                found.push(file(storage, size));
+            }
+        }
+
+        let user = used_by.get(&cache_path);
+        for file in found {
+            match user {
+                Some(path) if !force => plan
+                    .skipped
+                    .push((file, format!("content is used by '{}'", path))),
+                _ => plan.candidates.push((file, cache_file.clone())),
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Check which of the `plan` candidates were removed by the command whose errors are `errors`
+pub fn report(
+    xvc_root: &XvcRoot,
+    plan: RemovePlan,
+    from_storage: Option<&str>,
+    errors: Option<String>,
+) -> PyResult<RemoveReport> {
+    let storage_paths = match from_storage {
+        Some(storage) => Some(store::storage_paths(xvc_root, storage)?),
+        None => None,
+    };
+    let message = errors.unwrap_or_else(|| "the file was not removed".to_string());
+
+    let mut report = RemoveReport {
+        skipped: plan.skipped,
+        ..Default::default()
+    };
+    for (file, cache_file) in plan.candidates {
+        let present = if file.location == "cache" {
+            cache_file.exists()
+        } else {
+            storage_paths
+                .as_ref()
+                .map(|paths| paths.contains(&file.cache_path))
+                .unwrap_or(false)
+        };
+        if present {
+            report.failures.push((file, message.clone()));
+        } else {
+            report.reclaimed_bytes += file.size.unwrap_or_default();
+            report.removed.push(file);
+        }
+    }
+    Ok(report)
+}
+
+/// Report of a dry run of `plan`
+pub fn dry_run(plan: RemovePlan) -> RemoveReport {
+    RemoveReport {
+        dry_run: true,
+        reclaimed_bytes: plan.candidates.iter().filter_map(|(f, _)| f.size).sum(),
+        removed: plan.candidates.into_iter().map(|(f, _)| f).collect(),
+        skipped: plan.skipped,
+        failures: Vec::new(),
+    }
+}
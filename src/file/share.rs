@@ -0,0 +1,155 @@
+//! Structured results of `file share`.
+
+use std::path::Path;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+/// A presigned URL for the content of a tracked file
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct SharedUrl {
+    pub path: String,
+    pub digest: String,
+    pub url: String,
+    pub storage: String,
+    /// Expiration time of the URL in ISO 8601 format, in UTC
+    pub expires_at: String,
+}
+
+#[pymethods]
+impl SharedUrl {
+    fn __repr__(&self) -> String {
+        format!(
+            "SharedUrl(path={:?}, storage={:?}, expires_at={:?})",
+            self.path, self.storage, self.expires_at
+        )
+    }
+
+    fn __str__(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Fields of the URL as a dict, as written to manifests
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("path", &self.path)?;
+        dict.set_item("digest", &self.digest)?;
+        dict.set_item("url", &self.url)?;
+        dict.set_item("storage", &self.storage)?;
+        dict.set_item("expires_at", &self.expires_at)?;
+        Ok(dict)
+    }
+}
+
+/// Parse a share duration like `24h` or `1h30m`, in seconds. Plain numbers are seconds. Accepted
+/// units are `s`, `m`, `h`, `d` and `w`.
+pub fn parse_duration(duration: &str) -> PyResult<u64> {
+    let duration = duration.trim();
+    let invalid = || PyValueError::new_err(format!("invalid duration '{}'", duration));
+    if let Ok(seconds) = duration.parse::<u64>() {
+        return Ok(seconds);
+    }
+    if duration.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0u64;
+    let mut number = String::new();
+    for c in duration.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let n = number.parse::<u64>().map_err(|_| invalid())?;
+        seconds = n
+            .checked_mul(multiplier)
+            .and_then(|s| s.checked_add(seconds))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    // A number without a unit after other parts, like `1h30`
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(seconds)
+}
+
+/// The time `seconds` from now in UTC, in ISO 8601 format
+pub fn expiration_time(py: Python, seconds: u64) -> PyResult<String> {
+    let datetime = py.import("datetime")?;
+    let now = datetime
+        .getattr("datetime")?
+        .call_method1("now", (datetime.getattr("timezone")?.getattr("utc")?,))?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("seconds", seconds)?;
+    let delta = datetime.getattr("timedelta")?.call((), Some(&kwargs))?;
+    now.call_method1("__add__", (delta,))?
+        .call_method0("isoformat")?
+        .extract()
+}
+
+/// The URL in the output of `file share`
+pub fn parse_url(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("https://") || l.starts_with("http://"))
+        .map(str::to_string)
+}
+
+/// Write `urls` to `manifest` as JSON or CSV, chosen by its extension
+pub fn write_manifest(py: Python, urls: &[SharedUrl], manifest: &Path) -> PyResult<()> {
+    let extension = manifest
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !matches!(extension.as_str(), "json" | "csv") {
+        return Err(PyValueError::new_err(format!(
+            "manifest must be a .json or .csv file, not '{}'",
+            manifest.to_string_lossy()
+        )));
+    }
+    let rows = urls
+        .iter()
+        .map(|u| u.to_dict(py))
+        .collect::<PyResult<Vec<_>>>()?;
+    let rows = PyList::new(py, rows)?;
+
+    let builtins = py.import("builtins")?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("newline", "")?;
+    kwargs.set_item("encoding", "utf-8")?;
+    let file = builtins.call_method("open", (manifest, "w"), Some(&kwargs))?;
+    let result = if extension == "json" {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("indent", 2)?;
+        py.import("json")?
+            .call_method("dump", (&rows, &file), Some(&kwargs))
+            .map(|_| ())
+    } else {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item(
+            "fieldnames",
+            ["path", "digest", "url", "storage", "expires_at"],
+        )?;
+        let writer = py
+            .import("csv")?
+            .call_method("DictWriter", (&file,), Some(&kwargs))?;
+        writer
+            .call_method0("writeheader")
+            .and_then(|_| writer.call_method1("writerows", (&rows,)))
+            .map(|_| ())
+    };
+    file.call_method0("close")?;
+    result
+}
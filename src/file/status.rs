@@ -0,0 +1,132 @@
+//! Workspace status of tracked files.
+//!
+//! The status is built from the entries of `file list`, which compare the recorded and the
+//! actual digests of files, and the records in the workspace stores.
+
+use std::collections::HashSet;
+
+use pyo3::prelude::*;
+use xvc_rust::core::XvcRoot;
+
+use super::list::FileEntry;
+use super::store::{self, Records};
+
+/// Paths of tracked files grouped by their state in the workspace
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct FileStatus {
+    /// Tracked files whose content differs from the recorded version
+    pub modified: Vec<String>,
+    /// Tracked files that don't exist in the workspace
+    pub missing: Vec<String>,
+    /// Tracked files that aren't checked out with their recorded recheck method
+    pub recheck_changed: Vec<String>,
+    /// Files that are neither tracked nor ignored
+    pub untracked: Vec<String>,
+    /// Tracked files in the cache but not in [FileStatus::storage]
+    pub not_in_storage: Vec<String>,
+    /// The storage checked for `not_in_storage`, `None` if no storage was checked
+    pub storage: Option<String>,
+}
+
+#[pymethods]
+impl FileStatus {
+    /// Whether tracked files in the workspace are identical to their records, and in the storage
+    /// when one is checked
+    #[getter]
+    fn clean(&self) -> bool {
+        self.modified.is_empty()
+            && self.missing.is_empty()
+            && self.recheck_changed.is_empty()
+            && self.not_in_storage.is_empty()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FileStatus(modified={}, missing={}, recheck_changed={}, untracked={}, not_in_storage={})",
+            self.modified.len(),
+            self.missing.len(),
+            self.recheck_changed.len(),
+            self.untracked.len(),
+            self.not_in_storage.len()
+        )
+    }
+
+    fn __str__(&self) -> String {
+        [
+            ("modified", &self.modified),
+            ("missing", &self.missing),
+            ("recheck changed", &self.recheck_changed),
+            ("not in storage", &self.not_in_storage),
+            ("untracked", &self.untracked),
+        ]
+        .iter()
+        .flat_map(|(label, paths)| paths.iter().map(move |p| format!("{}: {}", label, p)))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// Whether the file type in the workspace differs from the one `recheck_method` creates.
+///
+/// Reflinks can't be distinguished from copies, so they are not reported.
+fn recheck_changed(entry: &FileEntry) -> bool {
+    match entry.recheck_method.as_deref() {
+        Some("copy") => matches!(entry.file_type.as_str(), "symlink" | "hardlink"),
+        Some("symlink") => entry.file_type != "symlink",
+        Some("hardlink") => entry.file_type != "hardlink",
+        _ => false,
+    }
+}
+
+/// Tracked paths among `entries` whose cache files exist but aren't found in `storage`
+fn not_in_storage(
+    xvc_root: &XvcRoot,
+    entries: &[FileEntry],
+    storage: &str,
+) -> PyResult<Vec<String>> {
+    let tracked: HashSet<String> = entries
+        .iter()
+        .filter(|e| e.recorded_digest.is_some())
+        .map(|e| e.path.clone())
+        .collect();
+    let files = Records::load(xvc_root, None)?.tracked_files(xvc_root, &tracked)?;
+    let storage_paths = store::storage_paths(xvc_root, storage)?;
+    Ok(files
+        .into_iter()
+        .filter(|f| f.is_cached() && !storage_paths.contains(&f.cache_path))
+        .map(|f| f.path)
+        .collect())
+}
+
+/// Group `entries` by their status, checking the cache files in `storage` if given
+pub fn status(
+    xvc_root: &XvcRoot,
+    entries: &[FileEntry],
+    storage: Option<String>,
+) -> PyResult<FileStatus> {
+    let mut status = FileStatus::default();
+    for entry in entries.iter().filter(|e| e.file_type != "directory") {
+        let path = entry.path.clone();
+        if entry.recorded_digest.is_none() {
+            if entry.file_type != "missing" {
+                status.untracked.push(path);
+            }
+        } else if entry.file_type == "missing" {
+            status.missing.push(path);
+        } else {
+            if recheck_changed(entry) {
+                status.recheck_changed.push(path.clone());
+            }
+            if entry.changed {
+                status.modified.push(path);
+            }
+        }
+    }
+
+    if let Some(storage) = storage {
+        status.not_in_storage = not_in_storage(xvc_root, entries, &storage)?;
+        status.storage = Some(storage);
+    }
+    Ok(status)
+}
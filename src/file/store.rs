@@ -0,0 +1,418 @@
+//! Records of tracked files, read from the Xvc stores in the workspace or at a Git reference.
+//!
+//! Stores at a reference are extracted from the Git object database to a temporary directory, so
+//! the working tree and the index are not modified.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use crossbeam_channel::unbounded;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use xvc_rust::core::{
+    AbsolutePath, ContentDigest, Error as XvcCoreError, RecheckMethod as XvcRecheckMethod,
+    XvcCachePath, XvcMetadata, XvcPath, XvcRoot,
+};
+use xvc_rust::ecs::{Error as EcsError, Storable, XvcEntity, XvcStore};
+use xvc_rust::storage::{get_storage_record, StorageIdentifier, XvcStorageOperations};
+use xvc_rust::Error as XvcError;
+
+use crate::{parse_value, Xvc, XvcPyError};
+
+fn core_error(e: XvcCoreError) -> PyErr {
+    XvcPyError(e.into()).into()
+}
+
+pub fn ecs_error(e: EcsError) -> PyErr {
+    XvcPyError(e.into()).into()
+}
+
+/// Run Git in the repository root and return its standard output
+pub fn git(xvc_root: &XvcRoot, args: &[&str]) -> PyResult<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(xvc_root.absolute_path().as_path())
+        .args(args)
+        .output()?;
+    git_output(args, output)
+}
+
+/// Run Git in the repository root with `input` as its standard input and return its standard
+/// output
+pub fn git_with_input(xvc_root: &XvcRoot, args: &[&str], input: Vec<u8>) -> PyResult<Vec<u8>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(xvc_root.absolute_path().as_path())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    // Written from another thread, as Git may fill the output pipe before reading all input
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer.join().expect("stdin writer")?;
+    git_output(args, output)
+}
+
+fn git_output(args: &[&str], output: Output) -> PyResult<Vec<u8>> {
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(PyRuntimeError::new_err(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// The commit SHA of `git_ref`, raising `ValueError` if it doesn't name a commit
+pub fn resolve_ref(xvc_root: &XvcRoot, git_ref: &str) -> PyResult<String> {
+    let spec = format!("{}^{{commit}}", git_ref);
+    git(xvc_root, &["rev-parse", "--verify", "--quiet", &spec])
+        .map(|sha| String::from_utf8_lossy(&sha).trim().to_string())
+        .map_err(|_| PyValueError::new_err(format!("unknown Git reference '{}'", git_ref)))
+}
+
+/// Directory name of the store of `T` in the store root
+fn store_name<T: Storable>() -> String {
+    format!("{}-store", T::type_description())
+}
+
+/// Paths of the stores read by [Records], relative to the repository root
+pub fn record_stores(xvc_root: &XvcRoot) -> Vec<String> {
+    let store_dir = xvc_root.store_dir().to_path_buf();
+    let root = xvc_root.absolute_path().to_path_buf();
+    let relative_store_dir = store_dir.strip_prefix(&root).unwrap_or(&store_dir);
+    [
+        store_name::<XvcPath>(),
+        store_name::<ContentDigest>(),
+        store_name::<XvcMetadata>(),
+        store_name::<XvcRecheckMethod>(),
+    ]
+    .iter()
+    .map(|name| relative_store_dir.join(name).to_string_lossy().to_string())
+    .collect()
+}
+
+/// Contents of `objects`, read with a single `git cat-file --batch`
+pub fn read_objects(xvc_root: &XvcRoot, objects: &[&str]) -> PyResult<Vec<Vec<u8>>> {
+    if objects.is_empty() {
+        return Ok(Vec::new());
+    }
+    let input = objects
+        .iter()
+        .map(|o| format!("{}\n", o))
+        .collect::<String>()
+        .into_bytes();
+    // Each object is written as `<oid> <type> <size>\n<content>\n`
+    let output = git_with_input(xvc_root, &["cat-file", "--batch"], input)?;
+    let mut rest = output.as_slice();
+    let mut contents = Vec::with_capacity(objects.len());
+    for object in objects {
+        let newline = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        let header = String::from_utf8_lossy(&rest[..newline]).to_string();
+        let Some(size) = header
+            .rsplit(' ')
+            .next()
+            .and_then(|s| s.parse::<usize>().ok())
+        else {
+            return Err(PyRuntimeError::new_err(format!(
+                "git cat-file: cannot read '{}': {}",
+                object, header
+            )));
+        };
+        let start = newline + 1;
+        let end = (start + size).min(rest.len());
+        contents.push(rest[start..end].to_vec());
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+    Ok(contents)
+}
+
+/// The stores read by [Records] as recorded in a set of commits.
+///
+/// Store files don't change once written, so each file is read from the Git object database
+/// once for all commits.
+pub struct CommittedStores {
+    /// Object ids and paths of the store files in each commit
+    trees: Vec<Vec<(String, String)>>,
+    contents: HashMap<String, Vec<u8>>,
+}
+
+impl CommittedStores {
+    pub fn read(xvc_root: &XvcRoot, commits: &[&str]) -> PyResult<Self> {
+        let pathspecs = record_stores(xvc_root);
+        let mut trees = Vec::with_capacity(commits.len());
+        for commit in commits {
+            let mut args = vec!["ls-tree", "-r", "-z", commit, "--"];
+            args.extend(pathspecs.iter().map(|p| p.as_str()));
+            let listing = git(xvc_root, &args)?;
+            let mut tree = Vec::new();
+            // Each entry is `<mode> <type> <oid>\t<path>`
+            for entry in listing.split(|b| *b == 0).filter(|e| !e.is_empty()) {
+                let entry = String::from_utf8_lossy(entry);
+                let Some((info, path)) = entry.split_once('\t') else {
+                    continue;
+                };
+                if let Some(oid) = info.split(' ').nth(2) {
+                    tree.push((oid.to_string(), path.to_string()));
+                }
+            }
+            trees.push(tree);
+        }
+
+        let mut seen = HashSet::new();
+        let objects: Vec<&str> = trees
+            .iter()
+            .flatten()
+            .map(|(oid, _)| oid.as_str())
+            .filter(|oid| seen.insert(*oid))
+            .collect();
+        let contents = objects
+            .iter()
+            .map(|oid| oid.to_string())
+            .zip(read_objects(xvc_root, &objects)?)
+            .collect();
+        Ok(Self { trees, contents })
+    }
+
+    /// Records of the commit at `index` in the commits given to [CommittedStores::read]
+    pub fn records(&self, index: usize) -> PyResult<Records> {
+        let dir = tempfile::Builder::new().prefix("xvc-store").tempdir()?;
+        for (oid, file) in &self.trees[index] {
+            let file = Path::new(file);
+            let name = file
+                .parent()
+                .and_then(|store| store.file_name())
+                .unwrap_or_default();
+            let path = dir
+                .path()
+                .join(name)
+                .join(file.file_name().unwrap_or_default());
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, &self.contents[oid])?;
+        }
+        Records::load_from(dir.path())
+    }
+}
+
+/// Records of tracked paths
+pub struct Records {
+    pub paths: XvcStore<XvcPath>,
+    pub digests: XvcStore<ContentDigest>,
+    pub metadata: XvcStore<XvcMetadata>,
+    pub recheck_methods: XvcStore<XvcRecheckMethod>,
+}
+
+impl Records {
+    /// Load the records in the workspace, or those committed in `commit`
+    pub fn load(xvc_root: &XvcRoot, commit: Option<&str>) -> PyResult<Self> {
+        match commit {
+            None => Self::load_from(xvc_root.store_dir()),
+            Some(commit) => CommittedStores::read(xvc_root, &[commit])?.records(0),
+        }
+    }
+
+    fn load_from(store_dir: &Path) -> PyResult<Self> {
+        Ok(Self {
+            paths: XvcStore::load_store(store_dir).map_err(ecs_error)?,
+            digests: XvcStore::load_store(store_dir).map_err(ecs_error)?,
+            metadata: XvcStore::load_store(store_dir).map_err(ecs_error)?,
+            recheck_methods: XvcStore::load_store(store_dir).map_err(ecs_error)?,
+        })
+    }
+
+    /// The entity of `xvc_path`
+    pub fn entity(&self, xvc_path: &XvcPath) -> Option<XvcEntity> {
+        self.paths
+            .iter()
+            .find(|(_, p)| *p == xvc_path)
+            .map(|(e, _)| *e)
+    }
+
+    /// The content digest recorded for `xvc_path`
+    pub fn digest(&self, xvc_path: &XvcPath) -> Option<ContentDigest> {
+        self.entity(xvc_path)
+            .and_then(|e| self.digests.get(&e))
+            .cloned()
+    }
+
+    /// The size recorded for `xvc_path`
+    pub fn size(&self, xvc_path: &XvcPath) -> Option<u64> {
+        self.entity(xvc_path)
+            .and_then(|e| self.metadata.get(&e))
+            .and_then(|m| m.size)
+    }
+
+    /// Tracked files with a recorded digest among `paths`, sorted by path
+    pub fn tracked_files(
+        &self,
+        xvc_root: &XvcRoot,
+        paths: &HashSet<String>,
+    ) -> PyResult<Vec<TrackedFile>> {
+        let mut files = Vec::new();
+        for (entity, xvc_path) in self.paths.iter() {
+            let path = xvc_path.to_string();
+            let Some(digest) = self.digests.get(entity) else {
+                continue;
+            };
+            if !paths.contains(&path) {
+                continue;
+            }
+            let cache_path = XvcCachePath::new(xvc_path, digest).map_err(core_error)?;
+            files.push(TrackedFile {
+                path,
+                xvc_path: xvc_path.clone(),
+                digest: digest.clone(),
+                size: self.metadata.get(entity).and_then(|m| m.size),
+                recheck_method: self.recheck_methods.get(entity).copied(),
+                cache_file: cache_path.to_absolute_path(xvc_root).to_path_buf(),
+                cache_path: cache_path.to_string(),
+            });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+}
+
+/// A tracked file and the location of its recorded content in the cache
+#[derive(Clone, Debug)]
+pub struct TrackedFile {
+    pub path: String,
+    pub xvc_path: XvcPath,
+    pub digest: ContentDigest,
+    pub size: Option<u64>,
+    pub recheck_method: Option<XvcRecheckMethod>,
+    /// Path of the content relative to the cache directory, also used in storages
+    pub cache_path: String,
+    /// Absolute path of the content in the cache
+    pub cache_file: PathBuf,
+}
+
+impl TrackedFile {
+    pub fn is_cached(&self) -> bool {
+        self.cache_file.exists()
+    }
+}
+
+/// The repository of an [Xvc] object, raising if it's not in an Xvc repository
+pub fn xvc_root(xvc: &Xvc) -> PyResult<XvcRoot> {
+    xvc.xvc_root_opt
+        .read()
+        .expect("lock xvc_root")
+        .clone()
+        .ok_or_else(|| XvcPyError(XvcError::RequiresXvcRepository).into())
+}
+
+/// The directory relative paths and targets are resolved against: the workdir of `xvc`, or the
+/// current directory if it's not set
+pub fn current_dir(xvc: &Xvc) -> PyResult<PathBuf> {
+    let current_dir = std::env::current_dir()?;
+    let dir = match &xvc.workdir {
+        Some(workdir) => current_dir.join(workdir),
+        None => current_dir,
+    };
+    Ok(fs::canonicalize(&dir).unwrap_or(dir))
+}
+
+/// `path` resolved against the workdir of `xvc`
+pub fn workdir_path(xvc: &Xvc, path: &Path) -> PyResult<PathBuf> {
+    Ok(current_dir(xvc)?.join(path))
+}
+
+/// The Xvc path of `path`, relative to the workdir of `xvc`
+pub fn xvc_path(xvc: &Xvc, xvc_root: &XvcRoot, path: &Path) -> PyResult<XvcPath> {
+    let current_dir = AbsolutePath::from(current_dir(xvc)?);
+    XvcPath::new(xvc_root, &current_dir, path).map_err(core_error)
+}
+
+/// `path`, relative to the repository root, as a target relative to the workdir of `xvc`
+pub fn target(xvc: &Xvc, xvc_root: &XvcRoot, path: &str) -> PyResult<String> {
+    let canonical = |p: PathBuf| fs::canonicalize(&p).unwrap_or(p);
+    let path = canonical(xvc_root.absolute_path().to_path_buf().join(path));
+    let current_dir = current_dir(xvc)?;
+    let common = path
+        .components()
+        .zip(current_dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in current_dir.components().skip(common) {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+    Ok(relative.to_string_lossy().to_string())
+}
+
+/// Targets for commands for `files`, relative to the working directory of `xvc`
+pub fn targets<'a>(
+    xvc: &Xvc,
+    xvc_root: &XvcRoot,
+    files: impl IntoIterator<Item = &'a TrackedFile>,
+) -> PyResult<Vec<String>> {
+    files
+        .into_iter()
+        .map(|f| target(xvc, xvc_root, &f.path))
+        .collect()
+}
+
+/// Absolute path of the cache file for `xvc_path` with `digest`
+pub fn cache_path(
+    xvc_root: &XvcRoot,
+    xvc_path: &XvcPath,
+    digest: &ContentDigest,
+) -> PyResult<PathBuf> {
+    let cache_path = XvcCachePath::new(xvc_path, digest).map_err(core_error)?;
+    Ok(cache_path.to_absolute_path(xvc_root).to_path_buf())
+}
+
+/// Paths of the files in a storage.
+///
+/// Storage paths contain the cache paths of the files, prefixed with the repository GUID. Each
+/// path is kept with its suffixes after a `/`, so cache paths are found without knowing the
+/// prefix.
+#[derive(Debug, Default)]
+pub struct StoragePaths(HashSet<String>);
+
+impl StoragePaths {
+    fn new(paths: impl Iterator<Item = String>) -> Self {
+        let mut suffixes = HashSet::new();
+        for path in paths {
+            suffixes.extend(
+                path.match_indices('/')
+                    .map(|(i, _)| path[i + 1..].to_string()),
+            );
+            suffixes.insert(path);
+        }
+        Self(suffixes)
+    }
+
+    /// Whether the content at `cache_path` is in the storage
+    pub fn contains(&self, cache_path: &str) -> bool {
+        self.0.contains(cache_path)
+    }
+}
+
+/// Paths of the files in `storage`
+pub fn storage_paths(xvc_root: &XvcRoot, storage: &str) -> PyResult<StoragePaths> {
+    let (output_snd, _output_rec) = unbounded();
+    let storage_identifier: StorageIdentifier = parse_value(storage, "storage")?;
+    let storage = get_storage_record(&output_snd, xvc_root, &storage_identifier)
+        .map_err(|e| XvcPyError(e.into()))?;
+    let listing = storage
+        .list(&output_snd, xvc_root)
+        .map_err(|e| XvcPyError(e.into()))?;
+    Ok(StoragePaths::new(
+        listing.paths.iter().map(|p| p.to_string()),
+    ))
+}
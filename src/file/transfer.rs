@@ -0,0 +1,296 @@
+//! Per-file progress and summaries of `file send` and `file bring`.
+//!
+//! Files are transferred with a single command, so Xvc can transfer them in parallel. The result
+//! of each file is read from the storage or the cache after the command, and from the errors in
+//! its output with [BatchErrors]. Events are passed to an optional Python callback and collected
+//! in a [TransferSummary].
+
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use xvc_rust::core::XvcRoot;
+
+use super::store::TrackedFile;
+use crate::output::output_errors;
+
+/// A step in the transfer of a file
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct TransferEvent {
+    /// One of `started`, `skipped`, `finished`, `failed`, or `excluded` and `planned` for
+    /// filtered and dry runs of `bring`
+    pub kind: String,
+    pub path: String,
+    /// Hex digest of the transferred content
+    pub digest: String,
+    /// Size of the file for `started` and `planned` events, bytes transferred for `finished`
+    /// events and 0 otherwise
+    pub bytes: u64,
+    /// Reason of `skipped` and `excluded` events and error message of `failed` events
+    pub message: Option<String>,
+}
+
+#[pymethods]
+impl TransferEvent {
+    fn __repr__(&self) -> String {
+        format!(
+            "TransferEvent(kind={:?}, path={:?}, bytes={})",
+            self.kind, self.path, self.bytes
+        )
+    }
+}
+
+/// Result of `XvcFile.send` and `XvcFile.bring`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct TransferSummary {
+    /// `send` or `bring`
+    pub operation: String,
+    pub storage: String,
+    /// Number of files transferred
+    pub transferred: usize,
+    /// Number of files that were already present at the destination
+    pub skipped: usize,
+    pub failed: usize,
+    /// Number of files left out by the filters of `bring`
+    pub excluded: usize,
+    /// Total bytes transferred
+    pub bytes: u64,
+    /// Whether this is the plan of a dry run, with nothing transferred
+    pub dry_run: bool,
+    /// Number of files a dry run would transfer
+    pub planned: usize,
+    /// Total bytes a dry run would transfer
+    pub planned_bytes: u64,
+    /// Duration of the transfer in seconds
+    pub duration: f64,
+    /// Paths and error messages of failed files
+    pub failures: Vec<(String, String)>,
+    pub events: Vec<TransferEvent>,
+}
+
+#[pymethods]
+impl TransferSummary {
+    fn __repr__(&self) -> String {
+        format!(
+            "TransferSummary(operation={:?}, transferred={}, skipped={}, failed={}, bytes={})",
+            self.operation, self.transferred, self.skipped, self.failed, self.bytes
+        )
+    }
+
+    fn __str__(&self) -> String {
+        let mut lines = if self.dry_run {
+            self.events
+                .iter()
+                .filter(|e| e.kind == "planned")
+                .map(|e| format!("{} {} {}", e.digest, e.bytes, e.path))
+                .chain(std::iter::once(format!(
+                    "{} (dry run): {} files, {} bytes would be transferred, {} skipped, {} excluded",
+                    self.operation, self.planned, self.planned_bytes, self.skipped, self.excluded
+                )))
+                .collect()
+        } else {
+            vec![format!(
+                "{}: {} transferred, {} skipped, {} excluded, {} failed, {} bytes in {:.2}s",
+                self.operation,
+                self.transferred,
+                self.skipped,
+                self.excluded,
+                self.failed,
+                self.bytes,
+                self.duration
+            )]
+        };
+        lines.extend(
+            self.failures
+                .iter()
+                .map(|(path, message)| format!("[ERROR] {}: {}", path, message)),
+        );
+        lines.join("\n")
+    }
+}
+
+/// Collects the events of a transfer and passes them to `callback`
+pub struct Progress<'py> {
+    callback: Option<Bound<'py, PyAny>>,
+    summary: TransferSummary,
+    start: Instant,
+}
+
+impl<'py> Progress<'py> {
+    pub fn new(operation: &str, storage: &str, callback: Option<Bound<'py, PyAny>>) -> Self {
+        Self {
+            callback,
+            summary: TransferSummary {
+                operation: operation.to_string(),
+                storage: storage.to_string(),
+                ..Default::default()
+            },
+            start: Instant::now(),
+        }
+    }
+
+    fn emit(
+        &mut self,
+        kind: &str,
+        file: &TrackedFile,
+        bytes: u64,
+        message: Option<&str>,
+    ) -> PyResult<()> {
+        let event = TransferEvent {
+            kind: kind.to_string(),
+            path: file.path.clone(),
+            digest: file.digest.digest().hex_str(),
+            bytes,
+            message: message.map(str::to_string),
+        };
+        if let Some(callback) = &self.callback {
+            callback.call1((event.clone(),))?;
+        }
+        self.summary.events.push(event);
+        Ok(())
+    }
+
+    pub fn started(&mut self, file: &TrackedFile) -> PyResult<()> {
+        self.emit("started", file, file.size.unwrap_or_default(), None)
+    }
+
+    pub fn skipped(&mut self, file: &TrackedFile, reason: &str) -> PyResult<()> {
+        self.summary.skipped += 1;
+        self.emit("skipped", file, 0, Some(reason))
+    }
+
+    pub fn finished(&mut self, file: &TrackedFile) -> PyResult<()> {
+        let bytes = file.size.unwrap_or_default();
+        self.summary.transferred += 1;
+        self.summary.bytes += bytes;
+        self.emit("finished", file, bytes, None)
+    }
+
+    pub fn excluded(&mut self, file: &TrackedFile, reason: &str) -> PyResult<()> {
+        self.summary.excluded += 1;
+        self.emit("excluded", file, 0, Some(reason))
+    }
+
+    pub fn planned(&mut self, file: &TrackedFile) -> PyResult<()> {
+        let bytes = file.size.unwrap_or_default();
+        self.summary.planned += 1;
+        self.summary.planned_bytes += bytes;
+        self.emit("planned", file, bytes, None)
+    }
+
+    pub fn failed(&mut self, file: &TrackedFile, message: &str) -> PyResult<()> {
+        self.summary.failed += 1;
+        self.summary
+            .failures
+            .push((file.path.clone(), message.to_string()));
+        self.emit("failed", file, 0, Some(message))
+    }
+
+    pub fn set_dry_run(&mut self) {
+        self.summary.dry_run = true;
+    }
+
+    pub fn finish(mut self) -> TransferSummary {
+        self.summary.duration = self.start.elapsed().as_secs_f64();
+        self.summary
+    }
+}
+
+/// Errors of a command run for a batch of files, from its output or the exception it raised
+pub struct BatchErrors(Option<String>);
+
+impl BatchErrors {
+    pub fn new(py: Python, result: &PyResult<String>) -> Self {
+        match result {
+            Ok(output) => Self(output_errors(output)),
+            Err(e) => Self(Some(e.value(py).to_string())),
+        }
+    }
+
+    /// Error lines mentioning the path or the cache path of `file`
+    pub fn of(&self, file: &TrackedFile) -> Option<String> {
+        let errors = self.0.as_deref()?;
+        let lines: Vec<&str> = errors
+            .lines()
+            .filter(|l| l.contains(&file.path) || l.contains(&file.cache_path))
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
+    /// Why `file` isn't found at the destination: its errors, all errors of the command if none
+    /// mentions it, or `default`
+    pub fn reason(&self, file: &TrackedFile, default: &str) -> String {
+        self.of(file)
+            .or_else(|| self.0.clone())
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Parse a size given as a number of bytes or with a `K`, `M`, `G` or `T` suffix, e.g. `500M`
+pub fn parse_size(value: &str) -> PyResult<u64> {
+    let value = value.trim();
+    let upper = value.to_uppercase();
+    let number = upper.trim_end_matches('B').trim_end_matches('I');
+    let (number, multiplier) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1u64 << 10),
+        Some('M') => (&number[..number.len() - 1], 1 << 20),
+        Some('G') => (&number[..number.len() - 1], 1 << 30),
+        Some('T') => (&number[..number.len() - 1], 1 << 40),
+        _ => (number, 1),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| *n >= 0.0)
+        .map(|n| (n * multiplier as f64) as u64)
+        .ok_or_else(|| PyValueError::new_err(format!("invalid size '{}'", value)))
+}
+
+/// Filters of `bring` that select files before they are transferred
+#[derive(Debug, Default)]
+pub struct BringFilter {
+    /// Files larger than this are excluded
+    pub max_size: Option<u64>,
+    /// Glob patterns matched against the paths relative to the repository root
+    pub exclude: Vec<String>,
+    /// Only bring files missing in the workspace
+    pub only_missing: bool,
+}
+
+impl BringFilter {
+    /// The reason `file` is excluded, `None` if it's selected
+    pub fn exclusion(
+        &self,
+        py: Python,
+        xvc_root: &XvcRoot,
+        file: &TrackedFile,
+    ) -> PyResult<Option<String>> {
+        if let (Some(max_size), Some(size)) = (self.max_size, file.size) {
+            if size > max_size {
+                return Ok(Some(format!("larger than max_size ({} bytes)", max_size)));
+            }
+        }
+        if !self.exclude.is_empty() {
+            let fnmatch = py.import("fnmatch")?;
+            for pattern in &self.exclude {
+                if fnmatch
+                    .call_method1("fnmatchcase", (&file.path, pattern))?
+                    .is_truthy()?
+                {
+                    return Ok(Some(format!("matches '{}'", pattern)));
+                }
+            }
+        }
+        if self.only_missing && file.xvc_path.to_absolute_path(xvc_root).exists() {
+            return Ok(Some("exists in the workspace".to_string()));
+        }
+        Ok(None)
+    }
+}
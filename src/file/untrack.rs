@@ -0,0 +1,274 @@
+//! Restoring versions of files before they are untracked.
+//!
+//! Versions are selected from the Git history of the records and copied from the cache to a
+//! directory. `file untrack` deletes the cache files of all versions recorded for the untracked
+//! paths, so they are linked aside in the `.xvc` directory and put back unless their removal is
+//! requested.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyFileNotFoundError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+use tempfile::TempDir;
+use xvc_rust::core::XvcRoot;
+use xvc_rust::ecs::Event;
+
+use super::history::{recorded_versions, RecordedVersion};
+use super::store::{self, ecs_error, Records, TrackedFile};
+
+/// Default name of restored versions, relative to the restore directory
+pub const DEFAULT_NAMING: &str = "{parent}/{stem}.{short_digest}{suffix}";
+
+/// A version of an untracked file copied from the cache
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct RestoredFile {
+    /// Path of the untracked file relative to the repository root
+    pub path: String,
+    /// Hex digest of the version
+    pub digest: String,
+    pub size: Option<u64>,
+    /// Path the version is restored to
+    pub restored_path: String,
+}
+
+#[pymethods]
+impl RestoredFile {
+    fn __repr__(&self) -> String {
+        format!(
+            "RestoredFile(path={:?}, digest={:?}, restored_path={:?})",
+            self.path, self.digest, self.restored_path
+        )
+    }
+
+    fn __fspath__(&self) -> String {
+        self.restored_path.clone()
+    }
+}
+
+/// Versions of `files` selected by `selectors`, or all recorded versions if there are none.
+///
+/// A selector is a Git reference, selecting the versions recorded in that commit, or a digest
+/// prefix of at least 3 characters, written with or without dashes. References are tried first.
+pub fn select_versions(
+    xvc_root: &XvcRoot,
+    files: &[TrackedFile],
+    selectors: Option<Vec<String>>,
+) -> PyResult<Vec<RecordedVersion>> {
+    let xvc_paths: Vec<_> = files.iter().map(|f| f.xvc_path.clone()).collect();
+    let Some(selectors) = selectors else {
+        return recorded_versions(xvc_root, &xvc_paths);
+    };
+
+    let mut all_versions = None;
+    let mut selected = Vec::<RecordedVersion>::new();
+    for selector in selectors {
+        let versions = if let Ok(commit) = store::resolve_ref(xvc_root, &selector) {
+            let records = Records::load(xvc_root, Some(&commit))?;
+            xvc_paths
+                .iter()
+                .filter_map(|xvc_path| {
+                    records.digest(xvc_path).map(|digest| RecordedVersion {
+                        xvc_path: xvc_path.clone(),
+                        digest,
+                        size: records.size(xvc_path),
+                    })
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let prefix = selector.replace('-', "").to_lowercase();
+            if prefix.len() < 3 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(PyValueError::new_err(format!(
+                    "'{}' is neither a Git reference nor a digest prefix of at least 3 characters",
+                    selector
+                )));
+            }
+            if all_versions.is_none() {
+                all_versions = Some(recorded_versions(xvc_root, &xvc_paths)?);
+            }
+            all_versions
+                .iter()
+                .flatten()
+                .filter(|v| v.digest.digest().hex_str().starts_with(&prefix))
+                .cloned()
+                .collect()
+        };
+        if versions.is_empty() {
+            return Err(PyValueError::new_err(format!(
+                "no version of the targets matches '{}'",
+                selector
+            )));
+        }
+        for version in versions {
+            if !selected
+                .iter()
+                .any(|v| v.xvc_path == version.xvc_path && v.digest == version.digest)
+            {
+                selected.push(version);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+/// The path of `version` in `dir`, named with the `naming` format string.
+///
+/// The format fields are `path`, `parent`, `name`, `stem` and `suffix` of the tracked path, and
+/// `digest` and `short_digest`, its first 8 characters.
+fn restored_path(
+    py: Python,
+    dir: &Path,
+    naming: &str,
+    version: &RecordedVersion,
+) -> PyResult<PathBuf> {
+    let path = version.xvc_path.to_string();
+    let relative = Path::new(&path);
+    let digest = version.digest.digest().hex_str();
+    let name = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = relative
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let suffix = relative
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = relative
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let fields = PyDict::new(py);
+    fields.set_item("path", &path)?;
+    fields.set_item("parent", parent)?;
+    fields.set_item("name", name)?;
+    fields.set_item("stem", stem)?;
+    fields.set_item("suffix", suffix)?;
+    fields.set_item("short_digest", &digest[..digest.len().min(8)])?;
+    fields.set_item("digest", &digest)?;
+    let name: String = PyString::new(py, naming)
+        .call_method("format", (), Some(&fields))?
+        .extract()?;
+    Ok(dir.join(name.trim_start_matches('/')))
+}
+
+/// Copy `versions` from the cache to `dir`. All versions must be in the cache.
+pub fn restore(
+    py: Python,
+    xvc_root: &XvcRoot,
+    versions: &[RecordedVersion],
+    dir: &Path,
+    naming: &str,
+) -> PyResult<Vec<RestoredFile>> {
+    let mut copies = Vec::new();
+    let mut missing = Vec::new();
+    for version in versions {
+        let cache_file = store::cache_path(xvc_root, &version.xvc_path, &version.digest)?;
+        if !cache_file.exists() {
+            missing.push(format!(
+                "{} ({})",
+                version.xvc_path,
+                version.digest.digest().hex_str()
+            ));
+        }
+        copies.push((
+            version,
+            cache_file,
+            restored_path(py, dir, naming, version)?,
+        ));
+    }
+    if !missing.is_empty() {
+        return Err(PyFileNotFoundError::new_err(format!(
+            "versions not in the cache, bring them from a storage first: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let mut restored = Vec::new();
+    for (version, cache_file, target) in copies {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&cache_file, &target)?;
+        // Cache files are read-only
+        let mut permissions = fs::metadata(&target)?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&target, permissions)?;
+        restored.push(RestoredFile {
+            path: version.xvc_path.to_string(),
+            digest: version.digest.digest().hex_str(),
+            size: version.size,
+            restored_path: target.to_string_lossy().to_string(),
+        });
+    }
+    Ok(restored)
+}
+
+/// Cache files kept aside while a command that deletes them runs
+pub struct CacheBackup {
+    dir: TempDir,
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl CacheBackup {
+    /// Link the cache files of all versions of `files` in the digest records to a directory in
+    /// `.xvc`, where links don't need copies
+    pub fn new(xvc_root: &XvcRoot, files: &[TrackedFile]) -> PyResult<Self> {
+        let records = Records::load(xvc_root, None)?;
+        let mut cache_files = BTreeSet::new();
+        for file in files {
+            let Some(entity) = records.entity(&file.xvc_path) else {
+                continue;
+            };
+            let events = records
+                .digests
+                .all_event_log_for_entity(entity)
+                .map_err(ecs_error)?;
+            for event in events.iter() {
+                if let Event::Add { value, .. } = event {
+                    cache_files.insert(store::cache_path(xvc_root, &file.xvc_path, value)?);
+                }
+            }
+        }
+
+        let dir = tempfile::Builder::new()
+            .prefix("untrack-backup")
+            .tempdir_in(xvc_root.xvc_dir())?;
+        let mut files = Vec::new();
+        for (i, cache_file) in cache_files.into_iter().enumerate() {
+            if !cache_file.exists() {
+                continue;
+            }
+            let backup = dir.path().join(i.to_string());
+            if fs::hard_link(&cache_file, &backup).is_err() {
+                fs::copy(&cache_file, &backup)?;
+            }
+            files.push((cache_file, backup));
+        }
+        Ok(Self { dir, files })
+    }
+
+    /// Put back the cache files that were deleted
+    pub fn restore(self) -> PyResult<()> {
+        for (cache_file, backup) in &self.files {
+            if cache_file.exists() {
+                continue;
+            }
+            if let Some(parent) = cache_file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if fs::hard_link(backup, cache_file).is_err() {
+                fs::copy(backup, cache_file)?;
+            }
+        }
+        drop(self.dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+//! Tracked files downloaded from HTTP URLs.
+//!
+//! The URL of a file and the `ETag` and `Last-Modified` headers of its last download are stored as
+//! a component of the file entity in their own store, like labels. Changes are detected with
+//! these headers. Files from servers that send neither are downloaded and compared on refresh.
+
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use pyo3::exceptions::{PyKeyError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use xvc_rust::core::{XvcPath, XvcRoot};
+use xvc_rust::ecs::{Storable, XvcStore};
+
+use super::store::{Records, TrackedFile};
+use crate::XvcPyError;
+
+/// Source of a file tracked from a URL
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UrlSource {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Storable for UrlSource {
+    fn type_description() -> String {
+        "url-source".to_string()
+    }
+}
+
+/// A tracked file and the URL it's downloaded from
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct TrackedUrl {
+    /// Path relative to the repository root
+    pub path: String,
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Whether the remote content differs from the tracked file, `None` if it's unknown
+    pub changed: Option<bool>,
+    /// Error of the request, if it failed
+    pub error: Option<String>,
+}
+
+#[pymethods]
+impl TrackedUrl {
+    fn __repr__(&self) -> String {
+        format!(
+            "TrackedUrl(path={:?}, url={:?}, changed={:?})",
+            self.path, self.url, self.changed
+        )
+    }
+}
+
+impl TrackedUrl {
+    pub fn new(path: String, source: UrlSource) -> Self {
+        Self {
+            path,
+            url: source.url,
+            etag: source.etag,
+            last_modified: source.last_modified,
+            changed: None,
+            error: None,
+        }
+    }
+}
+
+/// Time allowed to connect to a server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time allowed for a request, until the whole content is downloaded
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// An HTTP client for the requests of a call, reusing connections to the same server
+pub fn client() -> PyResult<Client> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+fn load(xvc_root: &XvcRoot) -> PyResult<XvcStore<UrlSource>> {
+    XvcStore::load_store(xvc_root.store_dir()).map_err(|e| XvcPyError(e.into()).into())
+}
+
+/// Raise `ValueError` unless `url` is an HTTP or HTTPS URL
+pub fn check_url(url: &str) -> PyResult<()> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if ["http", "https"].contains(&parsed.scheme()) => Ok(()),
+        _ => Err(PyValueError::new_err(format!(
+            "'{}' is not an HTTP or HTTPS URL",
+            url
+        ))),
+    }
+}
+
+/// Record `source` for the tracked `xvc_path`
+pub fn record(xvc_root: &XvcRoot, xvc_path: &XvcPath, source: UrlSource) -> PyResult<()> {
+    let entity = Records::load(xvc_root, None)?
+        .entity(xvc_path)
+        .ok_or_else(|| PyKeyError::new_err(format!("'{}' is not tracked", xvc_path)))?;
+    let mut store = load(xvc_root)?;
+    store.insert(entity, source);
+    store
+        .save(xvc_root.store_dir())
+        .map_err(|e| XvcPyError(e.into()))?;
+    Ok(())
+}
+
+/// Files among `files` tracked from URLs, with their sources
+pub fn sources(
+    xvc_root: &XvcRoot,
+    files: Vec<TrackedFile>,
+) -> PyResult<Vec<(TrackedFile, UrlSource)>> {
+    let records = Records::load(xvc_root, None)?;
+    let store = load(xvc_root)?;
+    Ok(files
+        .into_iter()
+        .filter_map(|file| {
+            let source = store.get(&records.entity(&file.xvc_path)?)?.clone();
+            Some((file, source))
+        })
+        .collect())
+}
+
+fn header(response: &Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Whether the validators of `source` and `current` show a change, `None` if they don't have a
+/// common one
+fn validators_changed(source: &UrlSource, current: &UrlSource) -> Option<bool> {
+    match (&source.etag, &current.etag) {
+        (Some(recorded), Some(current)) => Some(recorded != current),
+        _ => match (&source.last_modified, &current.last_modified) {
+            (Some(recorded), Some(current)) => Some(recorded != current),
+            _ => None,
+        },
+    }
+}
+
+/// Compare the headers of the URL of `source` with those recorded, without downloading the
+/// content
+pub fn check(client: &Client, source: &UrlSource) -> Result<Option<bool>, String> {
+    let response = client
+        .head(&source.url)
+        .send()
+        .and_then(Response::error_for_status)
+        .map_err(|e| e.to_string())?;
+    let current = UrlSource {
+        url: source.url.clone(),
+        etag: header(&response, ETAG),
+        last_modified: header(&response, LAST_MODIFIED),
+    };
+    Ok(validators_changed(source, &current))
+}
+
+/// Content downloaded next to its target
+pub struct Download {
+    pub source: UrlSource,
+    pub temp_file: PathBuf,
+}
+
+impl Download {
+    /// Replace `path` with the downloaded content. Links to the cache are replaced, not written
+    /// through.
+    pub fn replace(self, path: &Path) -> PyResult<UrlSource> {
+        fs::rename(&self.temp_file, path)?;
+        Ok(self.source)
+    }
+
+    /// Whether the downloaded content is identical to the file at `path`
+    pub fn same_content(&self, path: &Path) -> PyResult<bool> {
+        if fs::metadata(&self.temp_file)?.len() != fs::metadata(path)?.len() {
+            return Ok(false);
+        }
+        let mut downloaded = BufReader::new(File::open(&self.temp_file)?);
+        let mut current = BufReader::new(File::open(path)?);
+        let (mut a, mut b) = ([0u8; 8192], [0u8; 8192]);
+        loop {
+            let read = downloaded.read(&mut a)?;
+            if read == 0 {
+                return Ok(true);
+            }
+            current.read_exact(&mut b[..read])?;
+            if a[..read] != b[..read] {
+                return Ok(false);
+            }
+        }
+    }
+
+    pub fn discard(self) {
+        let _ = fs::remove_file(&self.temp_file);
+    }
+}
+
+/// Download `url` to a temporary file next to `path`. With `previous`, the request is
+/// conditional and `None` is returned if the server reports the content is not modified.
+pub fn download(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    previous: Option<&UrlSource>,
+) -> Result<Option<Download>, String> {
+    let mut request = client.get(url);
+    if let Some(previous) = previous {
+        if let Some(etag) = &previous.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &previous.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().map_err(|e| e.to_string())?;
+    if previous.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let mut response = response.error_for_status().map_err(|e| e.to_string())?;
+
+    let source = UrlSource {
+        url: url.to_string(),
+        etag: header(&response, ETAG),
+        last_modified: header(&response, LAST_MODIFIED),
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_file = path.with_file_name(format!(".{}.xvc-download", name));
+    let result = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| File::create(&temp_file))
+        .map_err(|e| e.to_string())
+        .and_then(|mut file| response.copy_to(&mut file).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_file);
+        return Err(e);
+    }
+    Ok(Some(Download { source, temp_file }))
+}
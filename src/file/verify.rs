@@ -0,0 +1,281 @@
+//! Integrity of cache files and their checkouts in the workspace.
+//!
+//! Cache files must exist and symlinks and hardlinks must point to them. A deep check hashes
+//! cache files and copies and compares them with the recorded digests. Hardlinks are not hashed
+//! separately, as they share their content with the cache file. Files are hashed with the text or
+//! binary setting they were tracked with, and content that can't be read is reported separately
+//! from content that doesn't match.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use pyo3::prelude::*;
+use xvc_rust::core::{
+    ContentDigest, Error as XvcCoreError, RecheckMethod as XvcRecheckMethod,
+    TextOrBinary as XvcTextOrBinary, XvcRoot,
+};
+use xvc_rust::ecs::XvcStore;
+use xvc_rust::file::common::FileTextOrBinary;
+
+use super::duplicates::recheck_method_name;
+use super::store::{ecs_error, Records, TrackedFile};
+
+/// A cache file or a checkout that doesn't match its record
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct VerifyIssue {
+    /// Path relative to the repository root
+    pub path: String,
+    /// `missing_cache`, `corrupted_cache`, `missing`, `broken_link`, `modified`, or `hash_error`
+    /// if the content couldn't be hashed
+    pub kind: String,
+    pub recheck_method: Option<String>,
+    /// Recorded hex digest
+    pub expected: String,
+    /// Hex digest of the content, when it was hashed
+    pub actual: Option<String>,
+    /// Absolute path of the cache file
+    pub cache_file: String,
+    /// Whether the issue was repaired
+    pub repaired: bool,
+    /// Error of hashing or repairing
+    pub error: Option<String>,
+}
+
+#[pymethods]
+impl VerifyIssue {
+    fn __repr__(&self) -> String {
+        format!(
+            "VerifyIssue(path={:?}, kind={:?}, repaired={})",
+            self.path,
+            self.kind,
+            if self.repaired { "True" } else { "False" }
+        )
+    }
+}
+
+impl VerifyIssue {
+    pub fn is_in_cache(&self) -> bool {
+        matches!(self.kind.as_str(), "missing_cache" | "corrupted_cache")
+    }
+
+    /// Whether the content is unknown because hashing failed, so nothing can be repaired
+    pub fn is_hash_error(&self) -> bool {
+        self.kind == "hash_error"
+    }
+}
+
+/// Result of `XvcFile.verify`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct VerifyReport {
+    /// Number of tracked files checked
+    pub checked: usize,
+    /// Whether contents were hashed
+    pub deep: bool,
+    pub issues: Vec<VerifyIssue>,
+}
+
+#[pymethods]
+impl VerifyReport {
+    /// Whether all checked files match their records, after repairs
+    #[getter]
+    fn ok(&self) -> bool {
+        self.issues.iter().all(|i| i.repaired)
+    }
+
+    fn __len__(&self) -> usize {
+        self.issues.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "VerifyReport(checked={}, issues={}, deep={})",
+            self.checked,
+            self.issues.len(),
+            if self.deep { "True" } else { "False" }
+        )
+    }
+
+    fn __str__(&self) -> String {
+        self.issues
+            .iter()
+            .map(|i| {
+                let state = match (&i.error, i.repaired) {
+                    (_, true) => " (repaired)".to_string(),
+                    (Some(error), false) => format!(" ({})", error),
+                    (None, false) => String::new(),
+                };
+                format!("{}: {}{}", i.kind, i.path, state)
+            })
+            .chain(std::iter::once(format!(
+                "{} files checked, {} issues",
+                self.checked,
+                self.issues.iter().filter(|i| !i.repaired).count()
+            )))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(b).is_ok_and(|b| a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &fs::Metadata, _b: &Path) -> bool {
+    true
+}
+
+/// Issues of `file`, with the workspace at `root`
+fn check_file(
+    root: &Path,
+    file: &TrackedFile,
+    deep: bool,
+    text_or_binary: XvcTextOrBinary,
+) -> Vec<VerifyIssue> {
+    let expected = file.digest.digest().hex_str();
+    let algorithm = file.digest.digest().algorithm;
+    let hash = |path: &Path| {
+        ContentDigest::new(path, algorithm, text_or_binary)
+            .map(|d| d.digest().hex_str())
+            .map_err(|e: XvcCoreError| e.to_string())
+    };
+    let mut issues = Vec::new();
+    let mut issue = |kind: &str, hashed: Option<Result<String, String>>| {
+        let (kind, actual, error) = match hashed {
+            Some(Ok(actual)) => (kind, Some(actual), None),
+            Some(Err(error)) => ("hash_error", None, Some(error)),
+            None => (kind, None, None),
+        };
+        issues.push(VerifyIssue {
+            path: file.path.clone(),
+            kind: kind.to_string(),
+            recheck_method: file
+                .recheck_method
+                .map(recheck_method_name)
+                .map(str::to_string),
+            expected: expected.clone(),
+            actual,
+            cache_file: file.cache_file.to_string_lossy().to_string(),
+            repaired: false,
+            error,
+        })
+    };
+
+    if !file.cache_file.exists() {
+        issue("missing_cache", None);
+    } else if deep {
+        let hashed = hash(&file.cache_file);
+        if hashed.as_ref() != Ok(&expected) {
+            issue("corrupted_cache", Some(hashed));
+        }
+    }
+
+    let path = root.join(&file.path);
+    let Ok(metadata) = fs::symlink_metadata(&path) else {
+        issue("missing", None);
+        return issues;
+    };
+    match file.recheck_method {
+        Some(XvcRecheckMethod::Symlink) => {
+            let target = fs::canonicalize(&path).ok();
+            if !metadata.file_type().is_symlink()
+                || target.is_none()
+                || target != fs::canonicalize(&file.cache_file).ok()
+            {
+                issue("broken_link", None);
+            }
+        }
+        Some(XvcRecheckMethod::Hardlink) => {
+            if metadata.file_type().is_symlink() || !same_file(&metadata, &file.cache_file) {
+                issue("broken_link", None);
+            }
+        }
+        // Symlinks in place of copies are reported by `status()` as recheck method changes
+        _ if metadata.file_type().is_symlink() => {}
+        _ if deep => {
+            let hashed = hash(&path);
+            if hashed.as_ref() != Ok(&expected) {
+                issue("modified", Some(hashed));
+            }
+        }
+        _ => {
+            if file.size.is_some_and(|size| size != metadata.len()) {
+                issue("modified", None);
+            }
+        }
+    }
+    issues
+}
+
+/// The text or binary setting of each of `files` by path, the one recorded when it was tracked
+/// unless `text_or_binary` is given
+pub fn text_or_binary(
+    xvc_root: &XvcRoot,
+    files: &[TrackedFile],
+    text_or_binary: Option<XvcTextOrBinary>,
+) -> PyResult<HashMap<String, XvcTextOrBinary>> {
+    if let Some(text_or_binary) = text_or_binary {
+        return Ok(files
+            .iter()
+            .map(|f| (f.path.clone(), text_or_binary))
+            .collect());
+    }
+    let records = Records::load(xvc_root, None)?;
+    let recorded: XvcStore<FileTextOrBinary> =
+        XvcStore::load_store(xvc_root.store_dir()).map_err(ecs_error)?;
+    let entities: HashMap<String, _> = records
+        .paths
+        .iter()
+        .map(|(entity, xvc_path)| (xvc_path.to_string(), *entity))
+        .collect();
+    Ok(files
+        .iter()
+        .filter_map(|f| {
+            let setting = recorded.get(entities.get(&f.path)?)?;
+            Some((f.path.clone(), setting.as_inner()))
+        })
+        .collect())
+}
+
+/// Issues of `files`, with the workspace at `root`. Files without a setting in `text_or_binary`
+/// are hashed with automatic detection.
+pub fn check(
+    py: Python,
+    root: &Path,
+    files: &[TrackedFile],
+    deep: bool,
+    text_or_binary: &HashMap<String, XvcTextOrBinary>,
+) -> Vec<VerifyIssue> {
+    py.allow_threads(|| {
+        files
+            .iter()
+            .flat_map(|file| {
+                let text_or_binary = text_or_binary
+                    .get(&file.path)
+                    .copied()
+                    .unwrap_or(XvcTextOrBinary::Auto);
+                check_file(root, file, deep, text_or_binary)
+            })
+            .collect()
+    })
+}
+
+/// Delete the corrupted cache file of `issue`, so it can be brought again
+pub fn remove_cache_file(issue: &VerifyIssue) -> PyResult<()> {
+    let cache_file = Path::new(&issue.cache_file);
+    if !cache_file.exists() {
+        return Ok(());
+    }
+    // Cache files are read-only
+    let mut permissions = fs::metadata(cache_file)?.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(cache_file, permissions)?;
+    fs::remove_file(cache_file)?;
+    Ok(())
+}
@@ -1,20 +1,32 @@
 pub mod file;
+pub mod migrate;
+pub mod options;
 pub mod output;
 pub mod pipeline;
+pub mod schema;
 pub mod storage;
+pub mod table;
+pub mod targets;
 
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::RwLock;
 
 use file::XvcFile;
-use output::dispatch_with_root;
+use options::{merge_options, CheckIgnoreOptions, InitOptions, RootOptions};
+use output::{dispatch_with_root, init_logging, CommandContext};
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyTuple};
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use targets::TargetChunks;
 use xvc_rust::core::default_project_config;
 use xvc_rust::core::types::xvcroot::load_xvc_root;
 use xvc_rust::error::Error as XvcError;
 use xvc_rust::{cli, watch, AbsolutePath, XvcConfigParams, XvcRootOpt};
 
+pub use migrate::XvcMigrate;
 pub use pipeline::XvcPipeline;
 pub use storage::XvcStorage;
 
@@ -61,6 +73,7 @@ pub fn run_xvc(cmd: String) -> PyResult<String> {
     };
 
     watch!(cli_opts);
+    init_logging(cli_opts.quiet, cli_opts.verbosity, cli_opts.debug);
     let py_output = dispatch_with_root(&Arc::new(RwLock::new(xvc_root_opt)), cli_opts)?;
 
     Ok(py_output.output)
@@ -75,6 +88,39 @@ impl From<XvcPyError> for PyErr {
 #[pymodule]
 fn xvc(_py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<Xvc>()?;
+    options::add_enum_classes(m)?;
+    options::add_option_classes(m)?;
+    m.add_class::<targets::Manifest>()?;
+    m.add_class::<schema::CommandInfo>()?;
+    m.add_class::<schema::OptionInfo>()?;
+    m.add_class::<file::list::FileEntry>()?;
+    m.add_class::<file::list::FileListSummary>()?;
+    m.add_class::<file::list::FileList>()?;
+    m.add_class::<file::hash::Digest>()?;
+    m.add_class::<file::hash::HashResult>()?;
+    m.add_class::<file::history::FileVersion>()?;
+    m.add_class::<file::history::FileDiff>()?;
+    m.add_class::<file::status::FileStatus>()?;
+    m.add_class::<file::transfer::TransferEvent>()?;
+    m.add_class::<file::transfer::TransferSummary>()?;
+    m.add_class::<file::dataset::Dataset>()?;
+    m.add_class::<file::dataset::DatasetItem>()?;
+    m.add_class::<file::share::SharedUrl>()?;
+    m.add_class::<file::remove::RemovedFile>()?;
+    m.add_class::<file::remove::RemoveReport>()?;
+    m.add_class::<file::untrack::RestoredFile>()?;
+    m.add_class::<file::duplicates::DuplicateGroup>()?;
+    m.add_class::<file::duplicates::DuplicateReport>()?;
+    m.add_class::<file::policy::PolicyRule>()?;
+    m.add_class::<file::policy::PolicyDeviation>()?;
+    m.add_class::<file::policy::RecheckPolicy>()?;
+    m.add_class::<file::url::TrackedUrl>()?;
+    m.add_class::<file::verify::VerifyIssue>()?;
+    m.add_class::<file::verify::VerifyReport>()?;
+    m.add_class::<migrate::XvcMigrate>()?;
+    m.add_class::<migrate::MigrationReport>()?;
+    m.add_class::<migrate::lfs::LfsObject>()?;
+    m.add_class::<migrate::lfs::LfsMigrationReport>()?;
     m.add_function(wrap_pyfunction!(run_xvc, m)?)?;
     m.add_function(wrap_pyfunction!(version, m)?)?;
     Ok(())
@@ -112,6 +158,18 @@ impl Xvc {
 
         Ok(out.output)
     }
+
+    /// Options of the main command for running subcommands without a command line
+    fn context(&self, command_string: String) -> CommandContext {
+        CommandContext {
+            quiet: self.quiet.unwrap_or_default(),
+            verbosity: self.verbosity.unwrap_or_default(),
+            from_ref: self.from_ref.clone(),
+            to_branch: self.to_branch.clone(),
+            skip_git: self.skip_git.unwrap_or_default(),
+            command_string,
+        }
+    }
 }
 
 #[pymethods]
@@ -133,7 +191,7 @@ impl Xvc {
         verbosity: Option<u8>,
         quiet: Option<bool>,
         debug: Option<bool>,
-        workdir: Option<String>,
+        workdir: Option<PathBuf>,
         no_system_config: Option<bool>,
         no_user_config: Option<bool>,
         no_env_config: Option<bool>,
@@ -141,6 +199,7 @@ impl Xvc {
         from_ref: Option<String>,
         to_branch: Option<String>,
     ) -> PyResult<Self> {
+        let workdir = workdir.map(|w| w.to_string_lossy().to_string());
         let xvc_config_params = XvcConfigParams {
             current_dir: AbsolutePath::from(workdir.clone().unwrap_or_else(|| ".".to_owned())),
             include_system_config: !no_system_config.unwrap_or_default(),
@@ -163,6 +222,12 @@ impl Xvc {
         };
         watch!(&xvc_root_opt);
 
+        init_logging(
+            quiet.unwrap_or_default(),
+            verbosity.unwrap_or_default(),
+            debug.unwrap_or_default(),
+        );
+
         Ok(Self {
             xvc_config_params,
             verbosity,
@@ -236,15 +301,28 @@ impl Xvc {
         XvcStorage::init(self)
     }
 
+    /// Conversion of projects managed with other tools
+    #[getter]
+    fn migrate(&self) -> XvcMigrate {
+        XvcMigrate::new(self)
+    }
+
     #[pyo3(signature = (pipeline_name=None))]
     fn pipeline(&self, pipeline_name: Option<String>) -> PyResult<XvcPipeline> {
         XvcPipeline::init(self, pipeline_name)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn root(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
+    #[pyo3(signature = (options=None, **opts))]
+    fn root(
+        &self,
+        options: Option<&Bound<RootOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
         let mut cli_opts = self.cli()?;
         cli_opts.push("root".to_string());
+        check_opts(opts, &[&["absolute"]])?;
         update_cli_flag(opts, &mut cli_opts, &["absolute"], "--absolute")?;
         watch!(cli_opts);
         // assert!(self
@@ -256,14 +334,26 @@ impl Xvc {
         self.run(cli_opts)
     }
 
-    #[pyo3(signature = (*targets, **opts))]
+    #[pyo3(signature = (*targets, options=None, **opts))]
     fn check_ignore(
         &self,
         targets: &Bound<PyTuple>,
+        options: Option<&Bound<CheckIgnoreOptions>>,
         opts: Option<&Bound<PyDict>>,
     ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
         let mut cli_opts = self.cli()?;
         cli_opts.push("check-ignore".to_string());
+        check_opts(
+            opts,
+            &[
+                &["help"],
+                &["details"],
+                &["ignore_filename"],
+                &["non_matching"],
+            ],
+        )?;
 
         update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
         update_cli_flag(opts, &mut cli_opts, &["details"], "--details")?;
@@ -281,20 +371,52 @@ impl Xvc {
     }
 
     /// Initialize an Xvc project
-    #[pyo3(signature = (**opts))]
-    fn init(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
+    #[pyo3(signature = (options=None, **opts))]
+    fn init(
+        &self,
+        options: Option<&Bound<InitOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
         let mut cli_opts = self.cli()?;
         cli_opts.push("init".to_string());
+        check_opts(
+            opts,
+            &[&["help"], &["path"], &["no-git", "no_git"], &["force"]],
+        )?;
 
         update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
         update_cli_opt(opts, &mut cli_opts, &["path"], "--path")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-git"], "--no-git")?;
+        update_cli_flag(opts, &mut cli_opts, &["no-git", "no_git"], "--no-git")?;
         update_cli_flag(opts, &mut cli_opts, &["force"], "--force")?;
 
         watch!(self.xvc_root_opt.read().unwrap());
         self.run(cli_opts)
     }
 
+    /// List the commands of Xvc with their options, read from the command line definition
+    fn commands(&self) -> Vec<schema::CommandInfo> {
+        schema::command_infos()
+    }
+
+    /// Run any Xvc command by its path, e.g. `call(["file", "track"], "data/",
+    /// recheck_method="symlink")`.
+    ///
+    /// Keyword arguments are validated against the options listed in [Xvc::commands].
+    #[pyo3(signature = (command, *targets, **opts))]
+    fn call(
+        &self,
+        command: Vec<String>,
+        targets: &Bound<PyTuple>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let mut cli_opts = self.cli()?;
+        cli_opts.extend(schema::command_args(&command, targets, opts)?);
+        watch!(cli_opts);
+        self.run(cli_opts)
+    }
+
     /// Show help
     fn help(&self) -> PyResult<String> {
         let mut cli_opts = self.cli()?;
@@ -307,7 +429,17 @@ fn get_bool(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<bool
     if let Some(dict) = dict {
         for key in keys {
             if let Some(value) = dict.get_item(key)? {
-                return Ok(Some(value.extract::<bool>()?));
+                if value.is_none() {
+                    return Ok(None);
+                }
+                return match value.downcast::<PyBool>() {
+                    Ok(b) => Ok(Some(b.is_true())),
+                    Err(_) => Err(PyTypeError::new_err(format!(
+                        "option '{}' expects a bool, got {}",
+                        key,
+                        value.get_type().name()?
+                    ))),
+                };
             }
         }
     }
@@ -315,16 +447,119 @@ fn get_bool(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<bool
 }
 
 fn get_string(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<String>> {
+    match get_values(dict, keys)? {
+        Some(mut values) if values.len() == 1 => Ok(values.pop()),
+        Some(_) => Err(PyTypeError::new_err(format!(
+            "option '{}' expects a single value, got a sequence",
+            keys[0]
+        ))),
+        None => Ok(None),
+    }
+}
+
+fn get_values(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<Vec<String>>> {
     if let Some(dict) = dict {
         for key in keys {
             if let Some(value) = dict.get_item(key)? {
-                return Ok(Some(value.extract::<String>()?));
+                if value.is_none() {
+                    return Ok(None);
+                }
+                let mut values = Vec::new();
+                to_cli_values(&value, key, &mut values)?;
+                return Ok(Some(values));
             }
         }
     }
     Ok(None)
 }
 
+/// Convert a Python value to command line arguments.
+///
+/// Strings, `os.PathLike` objects, numbers, booleans, the option enums and `enum.Enum` members (by
+/// their value) are converted to a single argument. Lists and tuples are expanded element by
+/// element. Other types raise `TypeError` naming the option in `name`.
+fn to_cli_values(value: &Bound<PyAny>, name: &str, values: &mut Vec<String>) -> PyResult<()> {
+    let py = value.py();
+    if let Some(cli_value) = options::enum_cli_value(value) {
+        values.push(cli_value);
+    } else if value.is_instance(&py.import("enum")?.getattr("Enum")?)? {
+        to_cli_values(&value.getattr("value")?, name, values)?;
+    } else if value.is_instance_of::<PyString>() {
+        values.push(value.extract::<String>()?);
+    } else if let Ok(b) = value.downcast::<PyBool>() {
+        values.push(b.is_true().to_string());
+    } else if value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>() {
+        values.push(value.str()?.to_string());
+    } else if value.hasattr("__fspath__")? {
+        let path = value.extract::<PathBuf>()?;
+        values.push(path.to_string_lossy().to_string());
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        for item in value.try_iter()? {
+            let item = item?;
+            if item.is_instance_of::<PyList>() || item.is_instance_of::<PyTuple>() {
+                return Err(PyTypeError::new_err(format!(
+                    "'{}' doesn't accept nested sequences",
+                    name
+                )));
+            }
+            to_cli_values(&item, name, values)?;
+        }
+    } else {
+        return Err(PyTypeError::new_err(format!(
+            "'{}' expects str, os.PathLike, int, float, bool, enum or a list of them, got {}",
+            name,
+            value.get_type().name()?
+        )));
+    }
+    Ok(())
+}
+
+/// Returns whether the flag in `keys` is set
+fn get_flag(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<bool> {
+    Ok(get_bool(dict, keys)?.unwrap_or(false))
+}
+
+/// Parse the option in `keys` to the corresponding Xvc type
+fn get_parsed<T>(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match get_string(dict, keys)? {
+        Some(value) => parse_value(&value, keys[0]).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Parse `value` of the option `name` to the corresponding Xvc type, raising `ValueError` for
+/// invalid values
+fn parse_value<T>(value: &str, name: &str) -> PyResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    T::from_str(value).map_err(|e| {
+        PyValueError::new_err(format!("invalid value '{}' for '{}': {}", value, name, e))
+    })
+}
+
+/// Like [get_parsed] but raises `TypeError` when the option is missing
+fn get_required<T>(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    get_parsed(dict, keys)?
+        .ok_or_else(|| PyTypeError::new_err(format!("missing required option '{}'", keys[0])))
+}
+
+/// Convert the positional arguments of a command to target paths
+fn get_targets(tuple: &Bound<PyTuple>) -> PyResult<Vec<String>> {
+    let mut targets = Vec::new();
+    update_targets(tuple, &mut targets)?;
+    Ok(targets)
+}
+
 pub fn update_cli_flag(
     dict: Option<&Bound<PyDict>>,
     cli: &mut Vec<String>,
@@ -339,39 +574,126 @@ pub fn update_cli_flag(
     Ok(())
 }
 
+/// Add `cli_opt` with the value of the first key found in `dict`.
+///
+/// Sequence values repeat the option for each element.
 pub fn update_cli_opt(
     dict: Option<&Bound<PyDict>>,
     cli: &mut Vec<String>,
     keys: &[&str],
     cli_opt: &str,
 ) -> PyResult<()> {
-    if let Some(value) = get_string(dict, keys)? {
-        cli.push(cli_opt.to_string());
-        cli.push(value.to_string());
+    if let Some(values) = get_values(dict, keys)? {
+        for value in values {
+            cli.push(cli_opt.to_string());
+            cli.push(value);
+        }
     }
     Ok(())
 }
 
-pub fn update_cli_tuple(
-    dict: Option<&Bound<PyDict>>,
-    cli: &mut Vec<String>,
-    keys: (&str, &str),
-    cli_opt: &str,
-) -> PyResult<()> {
-    let (first_key, second_key) = keys;
-    if let Some(first_value) = get_string(dict, &[first_key])? {
-        if let Some(second_value) = get_string(dict, &[second_key])? {
-            cli.push(cli_opt.to_string());
-            cli.push(first_value.to_string());
-            cli.push(second_value.to_string());
-        }
+/// Add the positional arguments of a command to `cli` as targets.
+///
+/// Besides the values accepted by options, a target can be a [targets::Manifest] or any
+/// iterable, e.g. a generator, which are read one item at a time.
+pub fn update_targets(tuple: &Bound<PyTuple>, cli: &mut Vec<String>) -> PyResult<()> {
+    let mut chunks = TargetChunks::new(tuple);
+    while let Some(chunk) = chunks.next_chunk()? {
+        cli.extend(chunk);
     }
     Ok(())
 }
 
-pub fn update_targets(tuple: &Bound<PyTuple>, cli: &mut Vec<String>) -> PyResult<()> {
-    for target in tuple.iter() {
-        cli.push(target.extract::<String>()?);
+/// Whether `value` is an iterable of targets, and not a single target that can be iterated
+fn is_target_iterable(value: &Bound<PyAny>) -> PyResult<bool> {
+    Ok(!value.is_instance_of::<PyString>()
+        && !value.is_instance_of::<PyBytes>()
+        && !value.is_instance_of::<PyDict>()
+        && !value.hasattr("__fspath__")?
+        && value.hasattr("__iter__")?)
+}
+
+/// Check the keyword arguments of a command against the options it accepts.
+///
+/// Each element of `options` lists the aliases of a single option, e.g. `&["recheck-method",
+/// "recheck_method"]`. An unknown key raises `TypeError` with the closest accepted key as a
+/// suggestion. Passing more than one alias of the same option also raises `TypeError`, as only one
+/// of them would be used.
+pub fn check_opts(dict: Option<&Bound<PyDict>>, options: &[&[&str]]) -> PyResult<()> {
+    let dict = match dict {
+        Some(dict) => dict,
+        None => return Ok(()),
+    };
+
+    for key in dict.keys() {
+        let key = key.extract::<String>()?;
+        if !options
+            .iter()
+            .any(|aliases| aliases.contains(&key.as_str()))
+        {
+            let message = match suggest_key(&key, options) {
+                Some(suggestion) => format!(
+                    "unexpected keyword argument '{}'. Did you mean '{}'?",
+                    key, suggestion
+                ),
+                None => format!("unexpected keyword argument '{}'", key),
+            };
+            return Err(PyTypeError::new_err(message));
+        }
+    }
+
+    for aliases in options {
+        let mut given = Vec::<&str>::new();
+        for alias in aliases.iter() {
+            if dict.contains(*alias)? {
+                given.push(*alias);
+            }
+        }
+        if given.len() > 1 {
+            return Err(PyTypeError::new_err(format!(
+                "keyword arguments {} are aliases of the same option. Pass only one of them.",
+                given
+                    .iter()
+                    .map(|k| format!("'{}'", k))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )));
+        }
     }
+
     Ok(())
 }
+
+/// Find the accepted key closest to `key`, if it's close enough to be a typo.
+fn suggest_key<'a>(key: &str, options: &[&[&'a str]]) -> Option<&'a str> {
+    let normalized = key.replace('-', "_");
+    options
+        .iter()
+        .flat_map(|aliases| aliases.iter())
+        .map(|candidate| {
+            (
+                edit_distance(&normalized, &candidate.replace('-', "_")),
+                *candidate,
+            )
+        })
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+        // Prefer the aliases usable as Python keywords
+        .min_by_key(|(distance, candidate)| (*distance, candidate.contains('-')))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,741 @@
+//! Converting projects managed with other tools to Xvc.
+//!
+//! DVC projects are converted by reading the outputs recorded in `.dvc` files and `dvc.lock`, and
+//! the stages in `dvc.yaml`. Outputs missing from the workspace are copied from the DVC cache, so
+//! nothing is downloaded from DVC remotes, and are then tracked with `XvcFile.track`. Stages become
+//! steps of the default pipeline. Git LFS pointer files are replaced with the objects in the local
+//! LFS store, see [lfs].
+
+pub mod lfs;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use pyo3::exceptions::{PyFileNotFoundError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use serde_yaml::{Mapping, Value};
+use xvc_rust::pipeline::pipeline::step::{StepCLI, StepSubCommand};
+use xvc_rust::pipeline::{PipelineCLI, PipelineSubCommand};
+
+use crate::file::{store, XvcFile};
+use crate::output::{dispatch_pipeline, raise_on_error};
+use crate::{get_targets, Xvc};
+use lfs::LfsMigrationReport;
+
+/// Result of `XvcMigrate.from_dvc`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct MigrationReport {
+    /// Paths tracked with Xvc, relative to the workdir of the `Xvc` object
+    pub tracked: Vec<String>,
+    /// Files copied from the DVC cache because they were missing from the workspace
+    pub restored: Vec<String>,
+    /// Names of the pipeline steps created from DVC stages
+    pub steps: Vec<String>,
+    /// Items that couldn't be converted, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+#[pymethods]
+impl MigrationReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "MigrationReport(tracked={}, restored={}, steps={}, skipped={})",
+            self.tracked.len(),
+            self.restored.len(),
+            self.steps.len(),
+            self.skipped.len()
+        )
+    }
+
+    fn __str__(&self) -> String {
+        std::iter::once(format!(
+            "{} paths tracked, {} files restored from the DVC cache, {} steps created",
+            self.tracked.len(),
+            self.restored.len(),
+            self.steps.len()
+        ))
+        .chain(
+            self.skipped
+                .iter()
+                .map(|(item, reason)| format!("  skipped {}: {}", item, reason)),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+impl MigrationReport {
+    fn skip(&mut self, item: impl ToString, reason: impl ToString) {
+        self.skipped.push((item.to_string(), reason.to_string()));
+    }
+}
+
+/// An output recorded by DVC
+#[derive(Clone, Debug)]
+struct DvcOut {
+    /// Path relative to the workdir
+    path: PathBuf,
+    /// MD5 digest, ending with `.dir` for directories
+    md5: Option<String>,
+    /// Whether DVC keeps the output in its cache
+    cache: bool,
+}
+
+/// A stage defined in `dvc.yaml`
+#[derive(Clone, Debug)]
+struct DvcStage {
+    name: String,
+    /// Directory the command runs in, relative to the workdir
+    dir: PathBuf,
+    definition: Mapping,
+}
+
+/// Remove `.` and `..` components without resolving links
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        normalized
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn read_yaml(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_yaml::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Paths in a DVC list, with the options given for each. Items are either paths or mappings
+/// from a path to its options.
+fn entries(value: Option<&Value>) -> Vec<(String, Option<&Value>)> {
+    let Some(Value::Sequence(items)) = value else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .flat_map(|item| match item {
+            Value::String(path) => vec![(path.clone(), None)],
+            Value::Mapping(mapping) => mapping
+                .iter()
+                .filter_map(|(k, v)| k.as_str().map(|k| (k.to_string(), Some(v))))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Outputs with digests in a list of `.dvc` file or `dvc.lock` records, relative to `dir`
+fn recorded_outs(dir: &Path, value: Option<&Value>) -> Vec<DvcOut> {
+    let Some(Value::Sequence(items)) = value else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let path = item.get("path")?.as_str()?;
+            Some(DvcOut {
+                path: normalize(&dir.join(path)),
+                md5: item.get("md5").and_then(Value::as_str).map(str::to_string),
+                cache: item.get("cache").and_then(Value::as_bool).unwrap_or(true),
+            })
+        })
+        .collect()
+}
+
+/// `.dvc` and `dvc.yaml` files under `dir`, relative to `workdir` like `dir`, skipping the
+/// `.dvc`, `.git` and `.xvc` directories
+fn find_dvc_files(workdir: &Path, dir: &Path, found: &mut Vec<PathBuf>) -> PyResult<()> {
+    for entry in fs::read_dir(workdir.join(dir))? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if file_type.is_dir() {
+            if ![".dvc", ".git", ".xvc"].contains(&name.as_str()) {
+                find_dvc_files(workdir, &dir.join(&name), found)?;
+            }
+        } else if file_type.is_file() && (name.ends_with(".dvc") || name == "dvc.yaml") {
+            found.push(dir.join(&name));
+        }
+    }
+    Ok(())
+}
+
+/// The cache file of `md5`, in the DVC 3 or DVC 2 layout
+fn cache_file(cache_dir: &Path, md5: &str) -> Option<PathBuf> {
+    if md5.len() < 3 || !md5.is_char_boundary(2) {
+        return None;
+    }
+    let (prefix, rest) = md5.split_at(2);
+    [
+        cache_dir.join("files").join("md5").join(prefix).join(rest),
+        cache_dir.join(prefix).join(rest),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+fn copy_from_cache(cache_file: &Path, target: &Path) -> PyResult<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(cache_file, target)?;
+    // Cache files are read-only
+    let mut permissions = fs::metadata(target)?.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(target, permissions)?;
+    Ok(())
+}
+
+/// Copy `out` from the DVC cache if it's missing from the workspace. Returns whether the output
+/// is in the workspace afterwards.
+fn restore_out(
+    workdir: &Path,
+    cache_dir: &Path,
+    out: &DvcOut,
+    report: &mut MigrationReport,
+) -> PyResult<bool> {
+    let path = path_string(&out.path);
+    let out_path = workdir.join(&out.path);
+    let Some(md5) = &out.md5 else {
+        if out_path.exists() {
+            return Ok(true);
+        }
+        report.skip(path, "not in the workspace and has no recorded digest");
+        return Ok(false);
+    };
+
+    if md5.ends_with(".dir") {
+        let Some(manifest) = cache_file(cache_dir, md5) else {
+            if !out_path.exists() {
+                report.skip(path, "directory listing is not in the DVC cache");
+            }
+            return Ok(out_path.exists());
+        };
+        let listing: Vec<serde_json::Value> = serde_json::from_str(&fs::read_to_string(&manifest)?)
+            .map_err(|e| {
+                PyValueError::new_err(format!("invalid {}: {}", path_string(&manifest), e))
+            })?;
+        for item in listing {
+            let (Some(relpath), Some(md5)) = (
+                item.get("relpath").and_then(serde_json::Value::as_str),
+                item.get("md5").and_then(serde_json::Value::as_str),
+            ) else {
+                continue;
+            };
+            let target = out.path.join(relpath);
+            if workdir.join(&target).exists() {
+                continue;
+            }
+            match cache_file(cache_dir, md5) {
+                Some(cached) => {
+                    copy_from_cache(&cached, &workdir.join(&target))?;
+                    report.restored.push(path_string(&target));
+                }
+                None => report.skip(path_string(&target), "not in the DVC cache"),
+            }
+        }
+        return Ok(out_path.exists());
+    }
+
+    if out_path.exists() {
+        return Ok(true);
+    }
+    match cache_file(cache_dir, md5) {
+        Some(cached) => {
+            copy_from_cache(&cached, &out_path)?;
+            report.restored.push(path);
+            Ok(true)
+        }
+        None => {
+            report.skip(path, "not in the workspace or the DVC cache");
+            Ok(false)
+        }
+    }
+}
+
+/// A dependency or output added to the step of a DVC stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Element {
+    Url,
+    Glob,
+    File,
+    Param,
+    OutputFile,
+    OutputMetric,
+}
+
+impl Element {
+    fn subcommand_name(self) -> &'static str {
+        match self {
+            Element::OutputFile | Element::OutputMetric => "output",
+            _ => "dependency",
+        }
+    }
+
+    /// The step subcommand adding `value` to the step `step_name`
+    fn subcommand(self, step_name: &str, value: String) -> StepSubCommand {
+        let step_name = step_name.to_string();
+        let values = |element: Element| (self == element).then(|| vec![value.clone()]);
+        match self {
+            Element::OutputFile | Element::OutputMetric => StepSubCommand::Output {
+                step_name,
+                files: values(Element::OutputFile),
+                metrics: values(Element::OutputMetric),
+                images: None,
+            },
+            _ => StepSubCommand::Dependency {
+                step_name,
+                generics: None,
+                urls: values(Element::Url),
+                files: values(Element::File),
+                steps: None,
+                glob_items: None,
+                globs: values(Element::Glob),
+                params: values(Element::Param),
+                regex_items: None,
+                regexes: None,
+                line_items: None,
+                lines: None,
+                sqlite_query: None,
+            },
+        }
+    }
+}
+
+/// Convert DVC projects to Xvc, returned by `Xvc.migrate`
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct XvcMigrate {
+    xvc_opts: Xvc,
+}
+
+impl XvcMigrate {
+    pub fn new(xvc_opts: &Xvc) -> Self {
+        Self {
+            xvc_opts: xvc_opts.clone(),
+        }
+    }
+
+    /// Track `targets` with `XvcFile.track`, applying the recheck method policy
+    fn track(
+        &self,
+        py: Python,
+        targets: &[String],
+        recheck_method: Option<&Bound<PyAny>>,
+    ) -> PyResult<()> {
+        let kwargs = PyDict::new(py);
+        if let Some(recheck_method) = recheck_method {
+            kwargs.set_item("recheck_method", recheck_method)?;
+        }
+        Bound::new(py, XvcFile::new(&self.xvc_opts)?)?.call_method(
+            "track",
+            PyTuple::new(py, targets)?,
+            Some(&kwargs),
+        )?;
+        Ok(())
+    }
+
+    /// Run `subcommand` of `xvc pipeline step` in the default pipeline
+    fn run_step(&self, subcommand: StepSubCommand, command_string: String) -> PyResult<()> {
+        let pipeline_cli = PipelineCLI {
+            pipeline_name: None,
+            subcommand: PipelineSubCommand::Step(StepCLI { subcommand }),
+        };
+        let out = dispatch_pipeline(
+            &self.xvc_opts.xvc_root_opt,
+            &self.xvc_opts.context(command_string),
+            pipeline_cli,
+        )?;
+        raise_on_error(&out.output)
+    }
+
+    /// The command of `stage`, run in its directory
+    fn stage_command(
+        &self,
+        stage: &DvcStage,
+        workdir: &Path,
+        root_dir: &Path,
+    ) -> Result<String, String> {
+        let command = match stage.definition.get("cmd") {
+            Some(Value::String(command)) => command.clone(),
+            Some(Value::Sequence(commands)) => commands
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" && "),
+            _ => return Err("the stage has no command".to_string()),
+        };
+        let dir = normalize(&workdir.join(&stage.dir));
+        let relative = dir
+            .strip_prefix(root_dir)
+            .map_err(|_| "the stage directory is outside the repository".to_string())?;
+        if relative.as_os_str().is_empty() {
+            return Ok(command);
+        }
+        let relative = path_string(relative);
+        if relative
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./".contains(c))
+        {
+            Ok(format!("cd {} && {}", relative, command))
+        } else {
+            Ok(format!(
+                "cd '{}' && {}",
+                relative.replace('\'', "'\\''"),
+                command
+            ))
+        }
+    }
+
+    /// The dependencies and outputs of `stage` to add to its step
+    fn stage_elements(
+        &self,
+        stage: &DvcStage,
+        workdir: &Path,
+        report: &mut MigrationReport,
+    ) -> Vec<(Element, String)> {
+        let definition = &stage.definition;
+        let mut elements = Vec::new();
+        let mut add = |element: Element, value: String| elements.push((element, value));
+
+        for (dep, _) in entries(definition.get("deps")) {
+            if dep.starts_with("http://") || dep.starts_with("https://") {
+                add(Element::Url, dep);
+            } else if dep.contains("://") {
+                report.skip(
+                    format!("{} dependency {}", stage.name, dep),
+                    "only HTTP URLs are supported as dependencies",
+                );
+            } else {
+                let path = normalize(&stage.dir.join(&dep));
+                if workdir.join(&path).is_dir() {
+                    add(Element::Glob, format!("{}/**", path_string(&path)));
+                } else {
+                    add(Element::File, path_string(&path));
+                }
+            }
+        }
+
+        let default_params = path_string(&normalize(&stage.dir.join("params.yaml")));
+        for (param, keys) in entries(definition.get("params")) {
+            match keys {
+                // A key in `params.yaml`
+                None => add(Element::Param, format!("{}::{}", default_params, param)),
+                Some(Value::Sequence(keys)) if !keys.is_empty() => {
+                    let file = path_string(&normalize(&stage.dir.join(&param)));
+                    for key in keys.iter().filter_map(Value::as_str) {
+                        add(Element::Param, format!("{}::{}", file, key));
+                    }
+                }
+                // The whole parameter file
+                Some(_) => add(
+                    Element::File,
+                    path_string(&normalize(&stage.dir.join(&param))),
+                ),
+            }
+        }
+
+        for (key, element) in [
+            ("outs", Element::OutputFile),
+            ("metrics", Element::OutputMetric),
+            ("plots", Element::OutputFile),
+        ] {
+            for (out, _) in entries(definition.get(key)) {
+                let path = normalize(&stage.dir.join(&out));
+                if workdir.join(&path).is_dir() {
+                    report.skip(
+                        format!("{} output {}", stage.name, path_string(&path)),
+                        "directories can't be step outputs, the files are tracked instead",
+                    );
+                } else {
+                    add(element, path_string(&path));
+                }
+            }
+        }
+        elements
+    }
+
+    /// Create a step for each stage. Stages that fail to convert are reported and left out.
+    fn convert_stages(
+        &self,
+        stages: Vec<DvcStage>,
+        workdir: &Path,
+        report: &mut MigrationReport,
+    ) -> PyResult<()> {
+        if stages.is_empty() {
+            return Ok(());
+        }
+        let root_dir = store::xvc_root(&self.xvc_opts)?
+            .absolute_path()
+            .to_path_buf();
+        let mut names = HashSet::new();
+        for stage in stages {
+            if !names.insert(stage.name.clone()) {
+                report.skip(
+                    &stage.name,
+                    "a stage with the same name was already converted",
+                );
+                continue;
+            }
+            let command = match self.stage_command(&stage, workdir, &root_dir) {
+                Ok(command) => command,
+                Err(reason) => {
+                    report.skip(&stage.name, reason);
+                    continue;
+                }
+            };
+            let new_step = StepSubCommand::New {
+                step_name: stage.name.clone(),
+                command,
+                when: None,
+            };
+            let command_string = format!("xvc pipeline step new {}", stage.name);
+            if let Err(e) = self.run_step(new_step, command_string) {
+                report.skip(&stage.name, e);
+                continue;
+            }
+            for (element, value) in self.stage_elements(&stage, workdir, report) {
+                let command_string = format!(
+                    "xvc pipeline step {} {} {}",
+                    element.subcommand_name(),
+                    stage.name,
+                    value
+                );
+                let item = format!("{} {}", stage.name, value);
+                if let Err(e) =
+                    self.run_step(element.subcommand(&stage.name, value), command_string)
+                {
+                    report.skip(item, e);
+                }
+            }
+            report.steps.push(stage.name);
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl XvcMigrate {
+    /// Convert the DVC project in `path`, the current directory by default, to Xvc.
+    ///
+    /// Files recorded in `.dvc` files and `dvc.lock` are tracked, after copying those missing
+    /// from the workspace from the DVC cache. The stages in `dvc.yaml` files become pipeline steps
+    /// with their dependencies, parameters and outputs. Items that can't be converted, like
+    /// `foreach` stages and outputs missing from the cache, are listed in the report.
+    /// `recheck_method` is passed to `XvcFile.track`.
+    #[pyo3(signature = (path=None, recheck_method=None))]
+    fn from_dvc(
+        &self,
+        py: Python,
+        path: Option<PathBuf>,
+        recheck_method: Option<&Bound<PyAny>>,
+    ) -> PyResult<MigrationReport> {
+        let workdir = store::current_dir(&self.xvc_opts)?;
+        let dvc_root = path.unwrap_or_else(|| PathBuf::from("."));
+        let dvc_dir = workdir.join(&dvc_root).join(".dvc");
+        if !dvc_dir.is_dir() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "{} is not a DVC project",
+                dvc_root.to_string_lossy()
+            )));
+        }
+        let cache_dir = dvc_dir.join("cache");
+
+        let mut files = Vec::new();
+        find_dvc_files(&workdir, &dvc_root, &mut files)?;
+        files.sort();
+
+        let mut report = MigrationReport::default();
+        let mut outs = Vec::new();
+        let mut stages = Vec::new();
+        for file in files {
+            let name = path_string(&normalize(&file));
+            let content = match read_yaml(&workdir.join(&file)) {
+                Ok(content) => content,
+                Err(e) => {
+                    report.skip(name, e);
+                    continue;
+                }
+            };
+            let dir = file.parent().unwrap_or(Path::new("."));
+            let dir = match content.get("wdir").and_then(Value::as_str) {
+                Some(wdir) => normalize(&dir.join(wdir)),
+                None => normalize(dir),
+            };
+
+            if !name.ends_with("dvc.yaml") {
+                // Imports keep their source in the dependencies
+                for dep in content
+                    .get("deps")
+                    .and_then(Value::as_sequence)
+                    .into_iter()
+                    .flatten()
+                {
+                    let source = match dep.get("repo") {
+                        Some(repo) => repo.get("url"),
+                        None => dep.get("path").filter(|_| dep.get("md5").is_none()),
+                    };
+                    if let Some(source) = source.and_then(Value::as_str) {
+                        report.skip(
+                            &name,
+                            format!("imported from {}, tracked without its source", source),
+                        );
+                    }
+                }
+                outs.extend(recorded_outs(&dir, content.get("outs")));
+                continue;
+            }
+
+            // Digests of stage outputs are in the lock file next to `dvc.yaml`
+            let lock_file = file.with_file_name("dvc.lock");
+            let lock = if workdir.join(&lock_file).exists() {
+                read_yaml(&workdir.join(&lock_file)).unwrap_or_else(|e| {
+                    report.skip(path_string(&normalize(&lock_file)), e);
+                    Value::Null
+                })
+            } else {
+                Value::Null
+            };
+            if content.get("plots").is_some() {
+                report.skip(
+                    format!("{} plots", name),
+                    "plot definitions are not converted",
+                );
+            }
+            let Some(Value::Mapping(definitions)) = content.get("stages") else {
+                continue;
+            };
+            for (stage_name, definition) in definitions {
+                let (Some(stage_name), Some(definition)) =
+                    (stage_name.as_str(), definition.as_mapping())
+                else {
+                    continue;
+                };
+                if definition.contains_key("foreach") || definition.contains_key("matrix") {
+                    report.skip(stage_name, "foreach and matrix stages are not converted");
+                    continue;
+                }
+                let stage_dir = match definition.get("wdir").and_then(Value::as_str) {
+                    Some(wdir) => normalize(&dir.join(wdir)),
+                    None => dir.clone(),
+                };
+                let locked: HashMap<PathBuf, DvcOut> = recorded_outs(
+                    &stage_dir,
+                    lock.get("stages")
+                        .and_then(|s| s.get(stage_name))
+                        .and_then(|s| s.get("outs")),
+                )
+                .into_iter()
+                .map(|out| (out.path.clone(), out))
+                .collect();
+                for key in ["outs", "metrics", "plots"] {
+                    for (out, options) in entries(definition.get(key)) {
+                        let path = normalize(&stage_dir.join(&out));
+                        let cache = options
+                            .and_then(|o| o.get("cache"))
+                            .and_then(Value::as_bool)
+                            .unwrap_or(true);
+                        outs.push(DvcOut {
+                            md5: locked.get(&path).and_then(|o| o.md5.clone()),
+                            path,
+                            cache,
+                        });
+                    }
+                }
+                stages.push(DvcStage {
+                    name: stage_name.to_string(),
+                    dir: stage_dir,
+                    definition: definition.clone(),
+                });
+            }
+        }
+
+        let mut targets = Vec::new();
+        for out in outs.iter().filter(|o| o.cache) {
+            if restore_out(&workdir, &cache_dir, out, &mut report)? {
+                let target = path_string(&out.path);
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+        }
+        if !targets.is_empty() {
+            self.track(py, &targets, recheck_method)?;
+            report.tracked = targets;
+        }
+
+        self.convert_stages(stages, &workdir, &mut report)?;
+        Ok(report)
+    }
+
+    /// Replace Git LFS pointer files in `targets`, or in the whole repository, with their objects
+    /// from the local LFS store and track them with Xvc.
+    ///
+    /// Converted files are removed from the Git index, and `filter=lfs` rules of `.gitattributes`
+    /// files matching only converted files are removed. Pointers whose objects are not in the
+    /// store are left in Git and reported as missing. `recheck_method` is passed to
+    /// `XvcFile.track`.
+    #[pyo3(signature = (*targets, recheck_method=None))]
+    fn from_git_lfs(
+        &self,
+        py: Python,
+        targets: &Bound<PyTuple>,
+        recheck_method: Option<&Bound<PyAny>>,
+    ) -> PyResult<LfsMigrationReport> {
+        let xvc_root = store::xvc_root(&self.xvc_opts)?;
+        let pathspecs = get_targets(targets)?
+            .iter()
+            .map(|target| {
+                store::xvc_path(&self.xvc_opts, &xvc_root, Path::new(target)).map(|p| {
+                    match p.to_string() {
+                        p if p.is_empty() => ".".to_string(),
+                        p => p,
+                    }
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let mut report = LfsMigrationReport::default();
+        let pointers = lfs::find_pointers(&xvc_root, &pathspecs)?;
+        lfs::replace_pointers(&xvc_root, pointers, &mut report)?;
+        if report.converted.is_empty() {
+            return Ok(report);
+        }
+        lfs::remove_from_index(&xvc_root, &report.converted)?;
+        lfs::remove_rules(py, &xvc_root, &mut report)?;
+
+        let targets = report
+            .converted
+            .iter()
+            .map(|o| store::target(&self.xvc_opts, &xvc_root, &o.path))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.track(py, &targets, recheck_method)?;
+        Ok(report)
+    }
+
+    fn __repr__(&self) -> String {
+        "XvcMigrate()".to_string()
+    }
+}
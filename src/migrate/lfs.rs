@@ -0,0 +1,344 @@
+//! Git LFS pointer files replaced with their objects.
+//!
+//! Pointer files are looked up in the Git index, so files the LFS filter has already checked out
+//! are found too. Objects are read from the local LFS store and nothing is fetched from LFS
+//! servers.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pyo3::prelude::*;
+use xvc_rust::core::XvcRoot;
+
+use crate::file::store;
+
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+/// Pointer files are smaller than this
+const MAX_POINTER_SIZE: u64 = 1024;
+
+/// An LFS object referenced by a pointer file
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct LfsObject {
+    /// Path of the pointer file relative to the repository root
+    pub path: String,
+    /// SHA-256 digest of the object
+    pub oid: String,
+    pub size: u64,
+}
+
+#[pymethods]
+impl LfsObject {
+    fn __repr__(&self) -> String {
+        format!(
+            "LfsObject(path={:?}, oid={:?}, size={})",
+            self.path, self.oid, self.size
+        )
+    }
+}
+
+/// Result of `XvcMigrate.from_git_lfs`
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug, Default)]
+pub struct LfsMigrationReport {
+    /// Files replaced with their objects and tracked with Xvc
+    pub converted: Vec<LfsObject>,
+    /// Pointer files left in Git, as their objects are not in the local LFS store
+    pub missing: Vec<LfsObject>,
+    /// Removed `.gitattributes` rules, as `file: rule`
+    pub removed_rules: Vec<String>,
+}
+
+#[pymethods]
+impl LfsMigrationReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "LfsMigrationReport(converted={}, missing={}, removed_rules={})",
+            self.converted.len(),
+            self.missing.len(),
+            self.removed_rules.len()
+        )
+    }
+
+    fn __str__(&self) -> String {
+        std::iter::once(format!(
+            "{} files converted, {} objects missing, {} .gitattributes rules removed",
+            self.converted.len(),
+            self.missing.len(),
+            self.removed_rules.len()
+        ))
+        .chain(
+            self.missing
+                .iter()
+                .map(|o| format!("  missing {} ({})", o.path, o.oid)),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// The object ID and size in the content of a pointer file
+fn parse_pointer(content: &[u8]) -> Option<(String, u64)> {
+    let content = std::str::from_utf8(content).ok()?;
+    let mut lines = content.lines();
+    if lines.next()? != POINTER_VERSION {
+        return None;
+    }
+    let (mut oid, mut size) = (None, None);
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+    Some((oid?, size?))
+}
+
+/// Pointer files in the Git index matching `pathspecs`
+pub fn find_pointers(xvc_root: &XvcRoot, pathspecs: &[String]) -> PyResult<Vec<LfsObject>> {
+    let mut args = vec!["ls-files", "-z", "--stage", "--"];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let listing = store::git(xvc_root, &args)?;
+    let mut blobs = Vec::new();
+    for entry in listing.split(|b| *b == 0).filter(|e| !e.is_empty()) {
+        let entry = String::from_utf8_lossy(entry);
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut info = info.split(' ');
+        if let (Some(mode), Some(oid)) = (info.next(), info.next()) {
+            // Regular files, leaving out symlinks and submodules
+            if mode.starts_with("100") {
+                blobs.push((oid.to_string(), path.to_string()));
+            }
+        }
+    }
+    if blobs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let input = |blobs: &[(String, String)]| {
+        blobs
+            .iter()
+            .map(|(oid, _)| format!("{}\n", oid))
+            .collect::<String>()
+            .into_bytes()
+    };
+    let sizes = store::git_with_input(
+        xvc_root,
+        &["cat-file", "--batch-check=%(objectsize)"],
+        input(&blobs),
+    )?;
+    let blobs: Vec<_> = blobs
+        .into_iter()
+        .zip(String::from_utf8_lossy(&sizes).lines())
+        .filter(|(_, size)| size.parse::<u64>().is_ok_and(|s| s < MAX_POINTER_SIZE))
+        .map(|(blob, _)| blob)
+        .collect();
+    if blobs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Each object is written as `<oid> <type> <size>\n<content>\n`
+    let contents = store::git_with_input(xvc_root, &["cat-file", "--batch"], input(&blobs))?;
+    let mut rest = contents.as_slice();
+    let mut pointers = Vec::new();
+    for (_, path) in blobs {
+        let Some(newline) = rest.iter().position(|b| *b == b'\n') else {
+            break;
+        };
+        let header = String::from_utf8_lossy(&rest[..newline]).to_string();
+        let Some(size) = header
+            .rsplit(' ')
+            .next()
+            .and_then(|s| s.parse::<usize>().ok())
+        else {
+            rest = &rest[newline + 1..];
+            continue;
+        };
+        let start = newline + 1;
+        let end = (start + size).min(rest.len());
+        if let Some((oid, size)) = parse_pointer(&rest[start..end]) {
+            pointers.push(LfsObject { path, oid, size });
+        }
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+    Ok(pointers)
+}
+
+/// Directory of the local LFS store
+fn objects_dir(xvc_root: &XvcRoot) -> PyResult<PathBuf> {
+    let git_dir = store::git(xvc_root, &["rev-parse", "--git-common-dir"])?;
+    let git_dir = PathBuf::from(String::from_utf8_lossy(&git_dir).trim());
+    Ok(xvc_root
+        .absolute_path()
+        .to_path_buf()
+        .join(git_dir)
+        .join("lfs")
+        .join("objects"))
+}
+
+fn object_path(objects_dir: &Path, oid: &str) -> Option<PathBuf> {
+    if oid.len() != 64 || !oid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(objects_dir.join(&oid[..2]).join(&oid[2..4]).join(oid))
+}
+
+/// Copy the objects of `pointers` over the pointer files. Pointers with objects are added to
+/// `converted` and those without to `missing`.
+pub fn replace_pointers(
+    xvc_root: &XvcRoot,
+    pointers: Vec<LfsObject>,
+    report: &mut LfsMigrationReport,
+) -> PyResult<()> {
+    let objects_dir = objects_dir(xvc_root)?;
+    let root = xvc_root.absolute_path().to_path_buf();
+    for pointer in pointers {
+        let object = object_path(&objects_dir, &pointer.oid)
+            .filter(|o| fs::metadata(o).is_ok_and(|m| m.len() == pointer.size));
+        match object {
+            Some(object) => {
+                fs::copy(object, root.join(&pointer.path))?;
+                report.converted.push(pointer);
+            }
+            None => report.missing.push(pointer),
+        }
+    }
+    Ok(())
+}
+
+/// Remove `objects` from the Git index, keeping them in the workspace
+pub fn remove_from_index(xvc_root: &XvcRoot, objects: &[LfsObject]) -> PyResult<()> {
+    let paths = objects
+        .iter()
+        .flat_map(|o| o.path.bytes().chain(std::iter::once(0)))
+        .collect();
+    store::git_with_input(
+        xvc_root,
+        &[
+            "--literal-pathspecs",
+            "rm",
+            "--cached",
+            "--quiet",
+            "--pathspec-from-file=-",
+            "--pathspec-file-nul",
+        ],
+        paths,
+    )?;
+    Ok(())
+}
+
+/// Whether the `.gitattributes` `pattern` in `dir` matches `path`, both relative to the
+/// repository root. Patterns without a slash match file names in any directory.
+fn attribute_matches(
+    fnmatch: &Bound<PyModule>,
+    dir: &str,
+    pattern: &str,
+    path: &str,
+) -> PyResult<bool> {
+    let relative = if dir.is_empty() {
+        path
+    } else {
+        match path.strip_prefix(dir).and_then(|p| p.strip_prefix('/')) {
+            Some(relative) => relative,
+            None => return Ok(false),
+        }
+    };
+    let (name, pattern) = match pattern.strip_prefix('/') {
+        Some(pattern) => (relative, pattern),
+        None if pattern.contains('/') => (relative, pattern),
+        None => (relative.rsplit('/').next().unwrap_or(relative), pattern),
+    };
+    fnmatch
+        .call_method1("fnmatchcase", (name, pattern))?
+        .is_truthy()
+}
+
+/// Remove the `filter=lfs` rules of `.gitattributes` files that match converted files and no
+/// remaining pointer file in the index, and stage the changed files
+pub fn remove_rules(
+    py: Python,
+    xvc_root: &XvcRoot,
+    report: &mut LfsMigrationReport,
+) -> PyResult<()> {
+    let fnmatch = py.import("fnmatch")?;
+    // Pointers outside the migrated targets still need their rules
+    let converted: HashSet<&str> = report.converted.iter().map(|o| o.path.as_str()).collect();
+    let remaining: Vec<LfsObject> = find_pointers(xvc_root, &[".".to_string()])?
+        .into_iter()
+        .filter(|o| !converted.contains(o.path.as_str()))
+        .collect();
+    let root = xvc_root.absolute_path().to_path_buf();
+    let files = store::git(
+        xvc_root,
+        &[
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+            "--",
+            ":(glob)**/.gitattributes",
+        ],
+    )?;
+    let mut changed = Vec::new();
+    for file in files.split(|b| *b == 0).filter(|f| !f.is_empty()) {
+        let file = String::from_utf8_lossy(file).to_string();
+        let dir = file
+            .strip_suffix(".gitattributes")
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let path = root.join(&file);
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let mut kept = Vec::new();
+        let mut removed = false;
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().filter(|p| !p.starts_with('#'));
+            let lfs = fields.any(|f| f == "filter=lfs");
+            let remove = match pattern {
+                Some(pattern) if lfs => {
+                    let mut converted = false;
+                    for object in &report.converted {
+                        converted |= attribute_matches(&fnmatch, dir, pattern, &object.path)?;
+                    }
+                    let mut remains = false;
+                    for object in &remaining {
+                        remains |= attribute_matches(&fnmatch, dir, pattern, &object.path)?;
+                    }
+                    converted && !remains
+                }
+                _ => false,
+            };
+            if remove {
+                report
+                    .removed_rules
+                    .push(format!("{}: {}", file, line.trim()));
+                removed = true;
+            } else {
+                kept.push(line);
+            }
+        }
+        if removed {
+            let mut content = kept.join("\n");
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            fs::write(&path, content)?;
+            changed.push(file);
+        }
+    }
+
+    if !changed.is_empty() {
+        let mut args = vec!["add", "--"];
+        args.extend(changed.iter().map(String::as_str));
+        store::git(xvc_root, &args)?;
+    }
+    Ok(())
+}
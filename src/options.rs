@@ -0,0 +1,578 @@
+//! Typed option classes for the commands.
+//!
+//! Each class lists the options of a command as attributes, so they can be built once, inspected
+//! and passed to the command with `options=`. Keyword arguments given to the command along with
+//! an options object are merged into it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Options classes convert to the keyword arguments of their command
+pub trait CliOptions {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>>;
+}
+
+/// How a tracked file is checked out from the cache to the workspace
+#[pyclass(eq, eq_int, module = "xvc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecheckMethod {
+    Copy,
+    Hardlink,
+    Symlink,
+    Reflink,
+}
+
+impl RecheckMethod {
+    pub fn cli_value(&self) -> &'static str {
+        match self {
+            RecheckMethod::Copy => "copy",
+            RecheckMethod::Hardlink => "hardlink",
+            RecheckMethod::Symlink => "symlink",
+            RecheckMethod::Reflink => "reflink",
+        }
+    }
+}
+
+/// Whether to remove line endings before calculating the digest of a file
+#[pyclass(eq, eq_int, module = "xvc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextOrBinary {
+    Auto,
+    Text,
+    Binary,
+}
+
+impl TextOrBinary {
+    pub fn cli_value(&self) -> &'static str {
+        match self {
+            TextOrBinary::Auto => "auto",
+            TextOrBinary::Text => "text",
+            TextOrBinary::Binary => "binary",
+        }
+    }
+}
+
+/// Digest algorithm for content hashes
+#[pyclass(eq, eq_int, module = "xvc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Blake3,
+    Blake2s,
+    Sha2,
+    Sha3,
+}
+
+impl HashAlgorithm {
+    pub fn cli_value(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Blake2s => "blake2s",
+            HashAlgorithm::Sha2 => "sha2",
+            HashAlgorithm::Sha3 => "sha3",
+        }
+    }
+}
+
+/// Column templates for `file list`
+///
+/// Custom templates can still be passed as strings with `format=`.
+#[pyclass(eq, eq_int, module = "xvc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Type, recheck method, size, timestamp, short digests and name
+    Default,
+    /// Same as `Default` with full digests
+    Long,
+    /// Only the names
+    Name,
+}
+
+impl ListFormat {
+    pub fn cli_value(&self) -> &'static str {
+        match self {
+            ListFormat::Default => "{{aft}}{{rrm}} {{asz}} {{ats}} {{rcd8}} {{acd8}} {{name}}",
+            ListFormat::Long => "{{aft}}{{rrm}} {{asz}} {{ats}} {{rcd64}} {{acd64}} {{name}}",
+            ListFormat::Name => "{{name}}",
+        }
+    }
+}
+
+/// Sort order of `file list` results
+#[pyclass(eq, eq_int, module = "xvc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListSortCriteria {
+    Unsorted,
+    NameAsc,
+    NameDesc,
+    SizeAsc,
+    SizeDesc,
+    TimestampAsc,
+    TimestampDesc,
+}
+
+impl ListSortCriteria {
+    pub fn cli_value(&self) -> &'static str {
+        match self {
+            ListSortCriteria::Unsorted => "none",
+            ListSortCriteria::NameAsc => "name-asc",
+            ListSortCriteria::NameDesc => "name-desc",
+            ListSortCriteria::SizeAsc => "size-asc",
+            ListSortCriteria::SizeDesc => "size-desc",
+            ListSortCriteria::TimestampAsc => "t-asc",
+            ListSortCriteria::TimestampDesc => "t-desc",
+        }
+    }
+}
+
+macro_rules! cli_enum_methods {
+    ($($name:ident),*) => {
+        $(
+            #[pymethods]
+            impl $name {
+                /// The value passed to Xvc
+                #[getter]
+                fn value(&self) -> &'static str {
+                    self.cli_value()
+                }
+
+                fn __str__(&self) -> &'static str {
+                    self.cli_value()
+                }
+            }
+        )*
+
+        /// Returns the Xvc value if `value` is one of the option enums
+        pub fn enum_cli_value(value: &Bound<PyAny>) -> Option<String> {
+            $(
+                if let Ok(v) = value.extract::<$name>() {
+                    return Some(v.cli_value().to_string());
+                }
+            )*
+            None
+        }
+    };
+}
+
+cli_enum_methods!(
+    RecheckMethod,
+    TextOrBinary,
+    HashAlgorithm,
+    ListFormat,
+    ListSortCriteria
+);
+
+/// A size in bytes, or a string with a unit like `"500M"`
+#[derive(Clone, FromPyObject, IntoPyObject)]
+pub enum Size {
+    Bytes(u64),
+    Text(String),
+}
+
+impl fmt::Debug for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Size::Bytes(bytes) => write!(f, "{}", bytes),
+            Size::Text(text) => write!(f, "{:?}", text),
+        }
+    }
+}
+
+macro_rules! cli_options {
+    ($($(#[$meta:meta])* $name:ident { $($field:ident: $ty:ty),* $(,)? })*) => {
+        $(
+            $(#[$meta])*
+            #[pyclass(get_all, set_all, module = "xvc")]
+            #[derive(Clone, Debug, Default)]
+            pub struct $name {
+                $(pub $field: Option<$ty>,)*
+            }
+
+            #[pymethods]
+            impl $name {
+                #[new]
+                #[pyo3(signature = ($($field=None),*))]
+                #[allow(clippy::too_many_arguments)]
+                fn new($($field: Option<$ty>),*) -> Self {
+                    Self { $($field),* }
+                }
+
+                /// Keyword arguments equivalent to the options set in this object
+                #[pyo3(name = "to_dict")]
+                fn py_to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+                    CliOptions::to_dict(self, py)
+                }
+
+                fn __repr__(&self) -> String {
+                    let mut fields = Vec::<String>::new();
+                    $(
+                        if let Some(value) = &self.$field {
+                            fields.push(format!("{}={:?}", stringify!($field), value));
+                        }
+                    )*
+                    format!("{}({})", stringify!($name), fields.join(", "))
+                }
+            }
+
+            impl CliOptions for $name {
+                fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+                    let dict = PyDict::new(py);
+                    $(
+                        if let Some(value) = &self.$field {
+                            dict.set_item(stringify!($field), value.clone())?;
+                        }
+                    )*
+                    Ok(dict)
+                }
+            }
+        )*
+
+        pub fn add_option_classes(m: &Bound<PyModule>) -> PyResult<()> {
+            $(m.add_class::<$name>()?;)*
+            Ok(())
+        }
+    };
+}
+
+cli_options! {
+    /// Options for `Xvc.init`
+    InitOptions {
+        path: PathBuf,
+        no_git: bool,
+        force: bool,
+    }
+
+    /// Options for `Xvc.root`
+    RootOptions {
+        absolute: bool,
+    }
+
+    /// Options for `Xvc.check_ignore`
+    CheckIgnoreOptions {
+        details: bool,
+        ignore_filename: String,
+        non_matching: bool,
+    }
+
+    /// Options for `XvcFile.track`
+    TrackOptions {
+        recheck_method: RecheckMethod,
+        no_commit: bool,
+        text_or_binary: TextOrBinary,
+        force: bool,
+        no_parallel: bool,
+        include_git_files: bool,
+    }
+
+    /// Options for `XvcFile.hash`
+    HashOptions {
+        algorithm: HashAlgorithm,
+        text_or_binary: TextOrBinary,
+    }
+
+    /// Options for `XvcFile.carry_in`
+    CarryInOptions {
+        text_or_binary: TextOrBinary,
+        force: bool,
+        no_parallel: bool,
+    }
+
+    /// Options for `XvcFile.recheck`
+    RecheckOptions {
+        recheck_method: RecheckMethod,
+        force: bool,
+        no_parallel: bool,
+    }
+
+    /// Options for `XvcFile.list`
+    ListOptions {
+        format: ListFormat,
+        sort: ListSortCriteria,
+        no_summary: bool,
+        include_git_files: bool,
+        show_dirs: bool,
+        labels: BTreeMap<String, Option<String>>,
+    }
+
+    /// Options for `XvcFile.send`
+    SendOptions {
+        storage: String,
+        force: bool,
+    }
+
+    /// Options for `XvcFile.bring`
+    BringOptions {
+        storage: String,
+        force: bool,
+        no_recheck: bool,
+        recheck_as: RecheckMethod,
+        dry_run: bool,
+        max_size: Size,
+        exclude: Vec<String>,
+        only_missing: bool,
+    }
+
+    /// Options for `XvcFile.copy`
+    CopyOptions {
+        recheck_method: RecheckMethod,
+        force: bool,
+        no_recheck: bool,
+    }
+
+    /// Options for `XvcFile.mv`
+    MoveOptions {
+        recheck_method: RecheckMethod,
+        force: bool,
+        no_recheck: bool,
+    }
+
+    /// Options for `XvcFile.untrack`
+    UntrackOptions {
+        restore_dir: PathBuf,
+        versions: Vec<String>,
+        naming: String,
+        remove_cache: bool,
+    }
+
+    /// Options for `XvcFile.remove`
+    RemoveOptions {
+        force: bool,
+        from_cache: bool,
+        from_storage: String,
+        all_versions: bool,
+        only_version: String,
+        dry_run: bool,
+    }
+
+    /// Options for `XvcFile.share`
+    ShareOptions {
+        storage: String,
+        duration: String,
+        manifest: PathBuf,
+    }
+
+    /// Options for `XvcPipeline.new`
+    PipelineNewOptions {
+        pipeline_name: String,
+        workdir: PathBuf,
+    }
+
+    /// Options for `XvcPipeline.update`
+    PipelineUpdateOptions {
+        pipeline_name: String,
+        rename: String,
+        workdir: PathBuf,
+        set_default: bool,
+    }
+
+    /// Options for `XvcPipeline.delete`
+    PipelineDeleteOptions {
+        pipeline_name: String,
+    }
+
+    /// Options for `XvcPipeline.run`
+    PipelineRunOptions {
+        pipeline_name: String,
+    }
+
+    /// Options for `XvcPipeline.dag`
+    PipelineDagOptions {
+        pipeline_name: String,
+        file: PathBuf,
+        format: String,
+    }
+
+    /// Options for `XvcPipeline.export`
+    PipelineExportOptions {
+        pipeline_name: String,
+        file: PathBuf,
+        format: String,
+    }
+
+    /// Options for `XvcPipeline.import_pipeline`
+    PipelineImportOptions {
+        pipeline_name: String,
+        file: PathBuf,
+        format: String,
+        overwrite: bool,
+    }
+
+    /// Options for `XvcPipelineStep.new`
+    StepNewOptions {
+        step_name: String,
+        command: String,
+        when: String,
+    }
+
+    /// Options for `XvcPipelineStep.update`
+    StepUpdateOptions {
+        step_name: String,
+        command: String,
+        when: String,
+    }
+
+    /// Options for `XvcPipelineStep.dependency`
+    StepDependencyOptions {
+        step_name: String,
+        no_recheck: bool,
+        file: Vec<String>,
+        url: Vec<String>,
+        glob: Vec<String>,
+        glob_items: Vec<String>,
+        step: Vec<String>,
+        param: Vec<String>,
+        regex: Vec<String>,
+        regex_items: Vec<String>,
+        line: Vec<String>,
+        line_items: Vec<String>,
+        generic: Vec<String>,
+        sqlite_file: String,
+        sqlite_query: String,
+    }
+
+    /// Options for `XvcPipelineStep.output`
+    StepOutputOptions {
+        step_name: String,
+        no_recheck: bool,
+        file: Vec<String>,
+        metric: Vec<String>,
+        image: Vec<String>,
+    }
+
+    /// Options for `XvcPipelineStep.list`
+    StepListOptions {
+        names_only: bool,
+    }
+
+    /// Options for `XvcPipelineStep.show`
+    StepShowOptions {
+        step_name: String,
+    }
+
+    /// Options for `XvcStorage.new_local`
+    LocalStorageOptions {
+        name: String,
+        path: PathBuf,
+    }
+
+    /// Options for `XvcStorage.new_generic`
+    GenericStorageOptions {
+        name: String,
+        init: String,
+        list: String,
+        download: String,
+        upload: String,
+        delete: String,
+        processes: u32,
+        url: String,
+        storage_dir: String,
+    }
+
+    /// Options for `XvcStorage.new_rsync`
+    RsyncStorageOptions {
+        name: String,
+        host: String,
+        port: u16,
+        user: String,
+        storage_dir: String,
+    }
+
+    /// Options for `XvcStorage.new_rclone`
+    RcloneStorageOptions {
+        name: String,
+        remote: String,
+        storage_prefix: String,
+    }
+
+    /// Options for `XvcStorage.new_s3`
+    S3StorageOptions {
+        name: String,
+        storage_prefix: String,
+        bucket_name: String,
+        region: String,
+    }
+
+    /// Options for `XvcStorage.new_minio`
+    MinioStorageOptions {
+        name: String,
+        storage_prefix: String,
+        bucket_name: String,
+        endpoint: String,
+        region: String,
+    }
+
+    /// Options for `XvcStorage.new_digital_ocean`
+    DigitalOceanStorageOptions {
+        name: String,
+        storage_prefix: String,
+        bucket_name: String,
+        region: String,
+    }
+
+    /// Options for `XvcStorage.new_r2`
+    R2StorageOptions {
+        name: String,
+        storage_prefix: String,
+        account_id: String,
+        bucket_name: String,
+    }
+
+    /// Options for `XvcStorage.new_gcs`
+    GcsStorageOptions {
+        name: String,
+        storage_prefix: String,
+        bucket_name: String,
+        region: String,
+    }
+
+    /// Options for `XvcStorage.new_wasabi`
+    WasabiStorageOptions {
+        name: String,
+        storage_prefix: String,
+        bucket_name: String,
+        endpoint: String,
+    }
+}
+
+pub fn add_enum_classes(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<RecheckMethod>()?;
+    m.add_class::<TextOrBinary>()?;
+    m.add_class::<HashAlgorithm>()?;
+    m.add_class::<ListFormat>()?;
+    m.add_class::<ListSortCriteria>()?;
+    Ok(())
+}
+
+/// Merge an options object with the keyword arguments of a command.
+///
+/// Setting the same option in both raises `TypeError`.
+pub fn merge_options<'py, T>(
+    options: Option<&Bound<'py, T>>,
+    opts: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Option<Bound<'py, PyDict>>>
+where
+    T: CliOptions + PyClass,
+{
+    let options = match options {
+        Some(options) => options,
+        None => return Ok(opts.cloned()),
+    };
+
+    let dict = options.borrow().to_dict(options.py())?;
+    if let Some(opts) = opts {
+        for (key, value) in opts.iter() {
+            if dict.contains(&key)? {
+                return Err(PyTypeError::new_err(format!(
+                    "'{}' is set both in options and as a keyword argument",
+                    key
+                )));
+            }
+            dict.set_item(key, value)?;
+        }
+    }
+    Ok(Some(dict))
+}
@@ -3,9 +3,9 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crossbeam::thread;
-use crossbeam_channel::bounded;
+use crossbeam_channel::unbounded;
 use log::LevelFilter;
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::PyResult;
 
 use xvc_config::XvcVerbosity;
@@ -20,40 +20,40 @@ use xvc_rust::{
 use crate::XvcPyError;
 use crate::{Result, XvcPyRootOpt};
 
-const CHANNEL_BOUND: usize = 10000;
-
 pub struct PyCommandOutput {
     pub output: String,
 }
 
+/// Options of the main command that apply to all subcommands
+#[derive(Clone, Debug)]
+pub struct CommandContext {
+    pub quiet: bool,
+    pub verbosity: u8,
+    pub from_ref: Option<String>,
+    pub to_branch: Option<String>,
+    pub skip_git: bool,
+    /// Used in the commit message of Git automation
+    pub command_string: String,
+}
+
+impl CommandContext {
+    fn term_log_level(&self) -> LevelFilter {
+        term_log_level(self.quiet, self.verbosity)
+    }
+}
+
 /// Runs the supplied xvc command.
 pub fn run(xvc_root_opt: &XvcPyRootOpt, args: &[&str]) -> PyResult<PyCommandOutput> {
     let cli_opts = XvcCLI::from_str_slice(args).map_err(XvcPyError)?;
     dispatch_with_root(xvc_root_opt, cli_opts)
 }
 
-/// Dispatch commands to respective functions in the API
-///
-/// It sets output verbosity with [XvcCLI::verbosity].
-/// Determines configuration sources by filling [XvcConfigInitParams].
-/// Tries to create an XvcRoot to determine whether we're inside one.
-/// Creates two threads: One for running the API function, one for getting strings from output
-/// channel.
-///
-/// A corresponding function to reuse the same [XvcRoot] object is [test_dispatch].
-/// It doesn't recreate the whole configuration and this prevents errors regarding multiple
-/// initializations.
-///
-/// The xvc_root_opt is passed within a cell to make it updatable in xvc init command. Otherwise
-/// the return value should be the same with sent value.
-pub fn dispatch_with_root(
-    xvc_root_opt: &Arc<RwLock<XvcRootOpt>>,
-    cli_opts: XvcCLI,
-) -> PyResult<PyCommandOutput> {
-    let verbosity = if cli_opts.quiet {
+/// Map the verbosity options to the log level of the terminal output
+pub fn term_log_level(quiet: bool, verbosity: u8) -> LevelFilter {
+    let verbosity = if quiet {
         XvcVerbosity::Quiet
     } else {
-        match cli_opts.verbosity {
+        match verbosity {
             0 => XvcVerbosity::Default,
             1 => XvcVerbosity::Warn,
             2 => XvcVerbosity::Info,
@@ -62,175 +62,223 @@ pub fn dispatch_with_root(
         }
     };
 
-    let term_log_level = match verbosity {
+    match verbosity {
         XvcVerbosity::Quiet => LevelFilter::Off,
         XvcVerbosity::Default => LevelFilter::Error,
         XvcVerbosity::Warn => LevelFilter::Warn,
         XvcVerbosity::Info => LevelFilter::Info,
         XvcVerbosity::Debug => LevelFilter::Debug,
         XvcVerbosity::Trace => LevelFilter::Trace,
-    };
+    }
+}
 
+/// Set up logging for the commands.
+///
+/// This is called once when an [crate::Xvc] object is created, not for each command.
+pub fn init_logging(quiet: bool, verbosity: u8, debug: bool) {
     setup_logging(
-        Some(term_log_level),
-        if cli_opts.debug {
+        Some(term_log_level(quiet, verbosity)),
+        if debug {
             Some(LevelFilter::Trace)
         } else {
             None
         },
     );
+}
 
-    let command_output = thread::scope(move |s| {
-        let (output_snd, output_rec) = bounded::<Option<XvcOutputLine>>(CHANNEL_BOUND);
-
-        let output_snd_clone = output_snd.clone();
-        let _output_rec_clone = output_rec.clone();
-
-        let output_thread = s.spawn(move |_| {
-            let mut output_str = String::new();
-            while let Ok(Some(output_line)) = output_rec.recv() {
-                // output_str.push_str(&output_line);
-                match term_log_level {
-                    LevelFilter::Off => match output_line {
-                        XvcOutputLine::Output(_) => {}
-                        XvcOutputLine::Info(_) => {}
-                        XvcOutputLine::Warn(_) => {}
-                        XvcOutputLine::Error(_) => {}
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Tick(_) => todo!(),
-                        XvcOutputLine::Debug(_) => {}
-                    },
-                    LevelFilter::Error => match output_line {
-                        XvcOutputLine::Output(m) => output_str.push_str(&m),
-                        XvcOutputLine::Info(_) => {}
-                        XvcOutputLine::Warn(_) => {}
-                        XvcOutputLine::Error(m) => output_str.push_str(&format!("[ERROR] {}", m)),
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Tick(_) => todo!(),
-                        XvcOutputLine::Debug(_) => {}
-                    },
-                    LevelFilter::Warn => match output_line {
-                        XvcOutputLine::Output(m) => output_str.push_str(&m),
-                        XvcOutputLine::Warn(m) => output_str.push_str(&format!("[WARN] {}", m)),
-                        XvcOutputLine::Error(m) => output_str.push_str(&format!("[ERROR] {}", m)),
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Info(_) => {}
-                        XvcOutputLine::Tick(_) => todo!(),
-                        XvcOutputLine::Debug(_) => {}
-                    },
-                    LevelFilter::Info => match output_line {
-                        XvcOutputLine::Output(m) => output_str.push_str(&m),
-                        XvcOutputLine::Info(m) => output_str.push_str(&format!("[INFO] {}", m)),
-                        XvcOutputLine::Warn(m) => output_str.push_str(&format!("[WARN] {}", m)),
-                        XvcOutputLine::Error(m) => output_str.push_str(&format!("[ERROR] {}", m)),
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Tick(_) => todo!(),
-                        XvcOutputLine::Debug(_) => {}
-                    },
-                    LevelFilter::Debug => match output_line {
-                        XvcOutputLine::Output(m) => output_str.push_str(&m),
-                        XvcOutputLine::Info(m) => output_str.push_str(&format!("[INFO] {}", m)),
-                        XvcOutputLine::Warn(m) => output_str.push_str(&format!("[WARN] {}", m)),
-                        XvcOutputLine::Error(m) => output_str.push_str(&format!("[ERROR] {}", m)),
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Debug(m) => output_str.push_str(&format!("[DEBUG] {}", m)),
-                        XvcOutputLine::Tick(_) => todo!(),
-                    },
-                    LevelFilter::Trace => match output_line {
-                        XvcOutputLine::Output(m) => output_str.push_str(&m),
-                        XvcOutputLine::Info(m) => output_str.push_str(&format!("[INFO] {}", m)),
-                        XvcOutputLine::Warn(m) => output_str.push_str(&format!("[WARN] {}", m)),
-                        XvcOutputLine::Error(m) => output_str.push_str(&format!("[ERROR] {}", m)),
-                        XvcOutputLine::Debug(m) => output_str.push_str(&format!("[DEBUG] {}", m)),
-                        XvcOutputLine::Panic(m) => output_str.push_str(&format!("[PANIC] {}", m)),
-                        XvcOutputLine::Tick(_) => todo!(),
-                    },
-                }
-            }
-            output_str
-        });
-
-        if let Some(from_ref) = cli_opts.from_ref {
-            let xvc_root_opt = xvc_root_opt.read().expect("Lock xvc_root").to_owned();
-            if let Some(ref xvc_root) = xvc_root_opt {
-                uwr!(
-                    git_checkout_ref(&output_snd, xvc_root, &from_ref),
-                    output_snd
-                );
-            }
+/// Dispatch commands to respective functions in the API
+///
+/// Determines the output level from [XvcCLI::verbosity] and [XvcCLI::quiet] and runs the command
+/// with [run_with_output].
+///
+/// The xvc_root_opt is passed within a cell to make it updatable in xvc init command. Otherwise
+/// the return value should be the same with sent value.
+pub fn dispatch_with_root(
+    xvc_root_opt: &Arc<RwLock<XvcRootOpt>>,
+    cli_opts: XvcCLI,
+) -> PyResult<PyCommandOutput> {
+    let context = CommandContext {
+        quiet: cli_opts.quiet,
+        verbosity: cli_opts.verbosity,
+        from_ref: cli_opts.from_ref.clone(),
+        to_branch: cli_opts.to_branch.clone(),
+        skip_git: cli_opts.skip_git,
+        command_string: cli_opts.command_string.clone(),
+    };
+
+    run_with_output(xvc_root_opt, &context, |output_snd| {
+        match cli_opts.command {
+            XvcSubCommand::Init(opts) => handle_init(
+                output_snd,
+                xvc_root_opt,
+                opts,
+                context.to_branch.as_deref(),
+                &context.command_string,
+            ),
+
+            // following commands can only be run inside a repository
+            XvcSubCommand::Root(opts) => handle_root(output_snd, xvc_root_opt, opts),
+
+            XvcSubCommand::CheckIgnore(opts) => handle_check_ignore(output_snd, xvc_root_opt, opts),
+
+            XvcSubCommand::File(opts) => handle_file(output_snd, xvc_root_opt, opts),
+
+            XvcSubCommand::Pipeline(opts) => handle_pipeline(output_snd, xvc_root_opt, opts),
+
+            XvcSubCommand::Storage(opts) => handle_storage(output_snd, xvc_root_opt, opts),
+
+            XvcSubCommand::_Comp(_) => output_snd
+                .send(Some(XvcOutputLine::Error(
+                    "This command only used for completions".into(),
+                )))
+                // FIXME: reuse Crossbeam errors to xvc::Error
+                .map_err(|e| XvcPyError(XvcCoreError::from(e).into())),
         }
+    })
+}
 
-        let command_thread = s
-            .spawn(move |_| -> PyResult<()> {
-                match cli_opts.command {
-                    XvcSubCommand::Init(opts) => {
-                        let to_branch = cli_opts.to_branch.as_deref();
-                        let xvc_cmd = cli_opts.command_string.as_ref();
-                        handle_init(&output_snd, xvc_root_opt, opts, to_branch, xvc_cmd)?;
-                    }
-
-                    // following commands can only be run inside a repository
-                    XvcSubCommand::Root(opts) => handle_root(&output_snd, xvc_root_opt, opts)?,
-
-                    XvcSubCommand::CheckIgnore(opts) => {
-                        handle_check_ignore(&output_snd, xvc_root_opt, opts)?
-                    }
-
-                    XvcSubCommand::File(opts) => handle_file(&output_snd, xvc_root_opt, opts)?,
-
-                    XvcSubCommand::Pipeline(opts) => {
-                        handle_pipeline(&output_snd, xvc_root_opt, opts)?
-                    }
-
-                    XvcSubCommand::Storage(opts) => {
-                        handle_storage(&output_snd, xvc_root_opt, opts)?
-                    }
-                    XvcSubCommand::_Comp(_) => {
-                        output_snd
-                            .send(Some(XvcOutputLine::Error(
-                                "This command only used for completions".into(),
-                            )))
-                            // FIXME: reuse Crossbeam errors to xvc::Error
-                            .map_err(|e| XvcPyError(XvcCoreError::from(e).into()))?;
-                    }
-                };
-
-                if !cli_opts.skip_git {
-                    let xvc_root_opt = xvc_root_opt.read().expect("lock xvc_root").to_owned();
-                    if let Some(xvc_root) = xvc_root_opt {
-                        xvc_root.record();
-                        handle_git_automation(
-                            &output_snd,
-                            &xvc_root,
-                            cli_opts.to_branch.as_deref(),
-                            &cli_opts.command_string,
-                            // FIXME: Handle this error more gracefully
-                        )
-                        .unwrap();
-                    }
-                }
-
-                Ok(())
-            })
-            .join();
-
-        match command_thread.unwrap() {
-            Ok(_) => {
-                debug!(output_snd_clone, "Command completed successfully.");
-            }
-            Err(e) => {
-                error!(output_snd_clone, "{}", e);
-            }
-        };
+/// Run a file command from its options, without parsing a command line.
+pub fn dispatch_file(
+    xvc_root_opt: &XvcPyRootOpt,
+    context: &CommandContext,
+    opts: file::XvcFileCLI,
+) -> PyResult<PyCommandOutput> {
+    run_with_output(xvc_root_opt, context, |output_snd| {
+        handle_file(output_snd, xvc_root_opt, opts)
+    })
+}
 
-        output_snd_clone.send(None).unwrap();
-        let output = output_thread.join().unwrap();
+/// Run a pipeline command from its options, without parsing a command line.
+pub fn dispatch_pipeline(
+    xvc_root_opt: &XvcPyRootOpt,
+    context: &CommandContext,
+    opts: pipeline::PipelineCLI,
+) -> PyResult<PyCommandOutput> {
+    run_with_output(xvc_root_opt, context, |output_snd| {
+        handle_pipeline(output_snd, xvc_root_opt, opts)
+    })
+}
 
-        Ok(PyCommandOutput { output })
+/// Run a storage command from its options, without parsing a command line.
+pub fn dispatch_storage(
+    xvc_root_opt: &XvcPyRootOpt,
+    context: &CommandContext,
+    opts: storage::StorageCLI,
+) -> PyResult<PyCommandOutput> {
+    run_with_output(xvc_root_opt, context, |output_snd| {
+        handle_storage(output_snd, xvc_root_opt, opts)
     })
-    .unwrap();
+}
+
+/// Run `command` in the current thread and collect its output.
+///
+/// Checks out [CommandContext::from_ref] before, and runs Git automation after the command unless
+/// [CommandContext::skip_git] is set. The output channel is unbounded, so the command doesn't
+/// need a concurrent reader. Lines are filtered by the verbosity in `context` after the command
+/// returns.
+pub fn run_with_output<F>(
+    xvc_root_opt: &XvcPyRootOpt,
+    context: &CommandContext,
+    command: F,
+) -> PyResult<PyCommandOutput>
+where
+    F: FnOnce(&XvcOutputSender) -> Result<()>,
+{
+    let (output_snd, output_rec) = unbounded::<Option<XvcOutputLine>>();
+
+    if let Some(from_ref) = &context.from_ref {
+        let xvc_root_opt = xvc_root_opt.read().expect("Lock xvc_root").to_owned();
+        if let Some(ref xvc_root) = xvc_root_opt {
+            uwr!(
+                git_checkout_ref(&output_snd, xvc_root, from_ref),
+                output_snd
+            );
+        }
+    }
+
+    let result = command(&output_snd).and_then(|_| {
+        if !context.skip_git {
+            let xvc_root_opt = xvc_root_opt.read().expect("lock xvc_root").to_owned();
+            if let Some(xvc_root) = xvc_root_opt {
+                xvc_root.record();
+                handle_git_automation(
+                    &output_snd,
+                    &xvc_root,
+                    context.to_branch.as_deref(),
+                    &context.command_string,
+                )
+                .map_err(|e: XvcCoreError| XvcPyError(e.into()))?;
+            }
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(_) => {
+            debug!(output_snd, "Command completed successfully.");
+        }
+        Err(e) => {
+            error!(output_snd, "{}", e.0);
+        }
+    };
+
+    output_snd.send(None).unwrap();
+
+    let term_log_level = context.term_log_level();
+    let mut output = String::new();
+    while let Ok(Some(output_line)) = output_rec.recv() {
+        append_output_line(&mut output, term_log_level, output_line);
+    }
+
+    Ok(PyCommandOutput { output })
+}
+
+/// The error lines of a command output, if there are any
+pub fn output_errors(output: &str) -> Option<String> {
+    let errors: Vec<&str> = output
+        .lines()
+        .filter(|l| l.starts_with("[ERROR]") || l.starts_with("[PANIC]"))
+        .collect();
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.join("\n"))
+    }
+}
+
+/// Raise `RuntimeError` with the error lines of a command output, if there are any
+pub fn raise_on_error(output: &str) -> PyResult<()> {
+    match output_errors(output) {
+        None => Ok(()),
+        Some(errors) => Err(PyRuntimeError::new_err(errors)),
+    }
+}
 
-    command_output
+/// Add `output_line` to `output` if it's visible in `term_log_level`.
+///
+/// Errors and panics are added in all levels, as [raise_on_error] and the methods returning
+/// results read them from the output.
+fn append_output_line(
+    output: &mut String,
+    term_log_level: LevelFilter,
+    output_line: XvcOutputLine,
+) {
+    match (output_line, term_log_level) {
+        (XvcOutputLine::Panic(m), _) => output.push_str(&format!("[PANIC] {}", m)),
+        (XvcOutputLine::Error(m), _) => output.push_str(&format!("[ERROR] {}", m)),
+        (_, LevelFilter::Off) => {}
+        (XvcOutputLine::Output(m), _) => output.push_str(&m),
+        (XvcOutputLine::Warn(m), level) if level >= LevelFilter::Warn => {
+            output.push_str(&format!("[WARN] {}", m))
+        }
+        (XvcOutputLine::Info(m), level) if level >= LevelFilter::Info => {
+            output.push_str(&format!("[INFO] {}", m))
+        }
+        (XvcOutputLine::Debug(m), level) if level >= LevelFilter::Debug => {
+            output.push_str(&format!("[DEBUG] {}", m))
+        }
+        (_, _) => {}
+    }
 }
 
 fn handle_storage(
@@ -1,8 +1,25 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
-
-use crate::{update_cli_flag, update_cli_opt};
-use crate::{update_cli_tuple, Xvc};
+use xvc_rust::pipeline::pipeline::step::{StepCLI, StepSubCommand};
+use xvc_rust::pipeline::{PipelineCLI, PipelineSubCommand};
+use xvc_rust::watch;
+
+use crate::options::{
+    merge_options, PipelineDagOptions, PipelineDeleteOptions, PipelineExportOptions,
+    PipelineImportOptions, PipelineNewOptions, PipelineRunOptions, PipelineUpdateOptions,
+    StepDependencyOptions, StepListOptions, StepNewOptions, StepOutputOptions, StepShowOptions,
+    StepUpdateOptions,
+};
+use crate::output::dispatch_pipeline;
+use crate::{
+    check_opts, get_flag, get_parsed, get_required, get_string, get_values, parse_value, Xvc,
+};
+
+/// Keys of the pipeline name option
+const PIPELINE_NAME: &[&str] = &["name", "pipeline_name", "pipeline-name"];
+/// Keys of the step name option
+const STEP_NAME: &[&str] = &["name", "step_name", "step-name"];
 
 #[pyclass]
 #[derive(Clone, Debug)]
@@ -30,147 +47,238 @@ impl XvcPipeline {
         Ok(cli_opts)
     }
 
-    fn xvc_run(&self, args: Vec<String>) -> PyResult<String> {
-        self.xvc_opts.run(args)
+    /// Show the help text of a subcommand
+    fn help(&self, subcommand: &[&str]) -> PyResult<String> {
+        let mut cli_opts = self.cli()?;
+        cli_opts.extend(subcommand.iter().map(|s| s.to_string()));
+        cli_opts.push("--help".to_string());
+        self.xvc_opts.run(cli_opts)
+    }
+
+    /// The command line equivalent of a subcommand, used in Git commit messages
+    fn command_string(&self, subcommand: &str, name: Option<&str>) -> String {
+        let mut command = "xvc pipeline".to_string();
+        if let Some(pipeline_name) = &self.pipeline_name {
+            command.push_str(&format!(" --pipeline-name {}", pipeline_name));
+        }
+        command.push(' ');
+        command.push_str(subcommand);
+        if let Some(name) = name {
+            command.push(' ');
+            command.push_str(name);
+        }
+        command
+    }
+
+    /// Run the subcommand with the API, without building a command line
+    fn run_subcommand(
+        &self,
+        subcommand: PipelineSubCommand,
+        command_string: String,
+    ) -> PyResult<String> {
+        watch!(subcommand);
+        let pipeline_cli = PipelineCLI {
+            pipeline_name: self.pipeline_name.clone(),
+            subcommand,
+        };
+        let out = dispatch_pipeline(
+            &self.xvc_opts.xvc_root_opt,
+            &self.xvc_opts.context(command_string),
+            pipeline_cli,
+        )?;
+        Ok(out.output)
     }
 }
 
 #[pymethods]
 impl XvcPipeline {
-    #[pyo3(signature = (**opts))]
+    #[pyo3(signature = (options=None, **opts))]
     #[allow(clippy::new_ret_no_self)]
     #[allow(clippy::wrong_self_convention)]
-    fn new(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    fn new(
+        &self,
+        options: Option<&Bound<PipelineNewOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], PIPELINE_NAME, &["workdir"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new"]);
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["workdir"], "--workdir")?;
-        self.xvc_run(cli_opts)
+        let pipeline_name: String = get_required(opts, PIPELINE_NAME)?;
+        let command = self.command_string("new", Some(&pipeline_name));
+        let subcommand = PipelineSubCommand::New {
+            pipeline_name,
+            workdir: get_parsed(opts, &["workdir"])?,
+            set_default: false,
+        };
+        self.run_subcommand(subcommand, command)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn update(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("update".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["rename"], "--rename")?;
-        update_cli_opt(opts, &mut cli_opts, &["workdir"], "--workdir")?;
-        update_cli_flag(
+    #[pyo3(signature = (options=None, **opts))]
+    fn update(
+        &self,
+        options: Option<&Bound<PipelineUpdateOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["set_default", "set-default"],
-            "--set-default",
+            &[
+                &["help"],
+                PIPELINE_NAME,
+                &["rename"],
+                &["workdir"],
+                &["set_default", "set-default"],
+            ],
         )?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["update"]);
+        }
+
+        let pipeline_name = get_string(opts, PIPELINE_NAME)?;
+        let command = self.command_string("update", pipeline_name.as_deref());
+        let subcommand = PipelineSubCommand::Update {
+            pipeline_name,
+            rename: get_string(opts, &["rename"])?,
+            workdir: get_parsed(opts, &["workdir"])?,
+            set_default: get_flag(opts, &["set_default", "set-default"])?,
+        };
+        self.run_subcommand(subcommand, command)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn delete(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("delete".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn delete(
+        &self,
+        options: Option<&Bound<PipelineDeleteOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], PIPELINE_NAME])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["delete"]);
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        self.xvc_run(cli_opts)
+        let pipeline_name: String = get_required(opts, PIPELINE_NAME)?;
+        let command = self.command_string("delete", Some(&pipeline_name));
+        self.run_subcommand(PipelineSubCommand::Delete { pipeline_name }, command)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn run(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("run".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn run(
+        &self,
+        options: Option<&Bound<PipelineRunOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], PIPELINE_NAME])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["run"]);
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        self.xvc_run(cli_opts)
+        let pipeline_name = get_string(opts, PIPELINE_NAME)?;
+        let command = self.command_string("run", pipeline_name.as_deref());
+        self.run_subcommand(PipelineSubCommand::Run { pipeline_name }, command)
     }
 
     #[pyo3(signature = (**opts))]
     fn list(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("list".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        check_opts(opts, &[&["help"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["list"]);
+        }
 
-        self.xvc_run(cli_opts)
+        self.run_subcommand(PipelineSubCommand::List, self.command_string("list", None))
     }
 
-    #[pyo3(signature = (**opts))]
-    fn dag(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("dag".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn dag(
+        &self,
+        options: Option<&Bound<PipelineDagOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], PIPELINE_NAME, &["file"], &["format"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["dag"]);
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["file"], "--file")?;
-        update_cli_opt(opts, &mut cli_opts, &["format"], "--format")?;
-        self.xvc_run(cli_opts)
+        let pipeline_name = get_string(opts, PIPELINE_NAME)?;
+        let command = self.command_string("dag", pipeline_name.as_deref());
+        let format = get_string(opts, &["format"])?.unwrap_or_else(|| "dot".to_string());
+        let subcommand = PipelineSubCommand::Dag {
+            pipeline_name,
+            file: get_parsed(opts, &["file"])?,
+            format: parse_value(&format, "format")?,
+        };
+        self.run_subcommand(subcommand, command)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn export(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("export".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn export(
+        &self,
+        options: Option<&Bound<PipelineExportOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], PIPELINE_NAME, &["file"], &["format"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["export"]);
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["file"], "--file")?;
-        update_cli_opt(opts, &mut cli_opts, &["format"], "--format")?;
-        self.xvc_run(cli_opts)
+        let pipeline_name = get_string(opts, PIPELINE_NAME)?;
+        let command = self.command_string("export", pipeline_name.as_deref());
+        let subcommand = PipelineSubCommand::Export {
+            pipeline_name,
+            file: get_parsed(opts, &["file"])?,
+            format: get_parsed(opts, &["format"])?,
+        };
+        self.run_subcommand(subcommand, command)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn import_pipeline(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("import".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn import_pipeline(
+        &self,
+        options: Option<&Bound<PipelineImportOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["name", "pipeline_name", "pipeline-name"],
-            "--pipeline-name",
+            &[
+                &["help"],
+                PIPELINE_NAME,
+                &["file"],
+                &["format"],
+                &["overwrite"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["file"], "--file")?;
-        update_cli_opt(opts, &mut cli_opts, &["format"], "--format")?;
-        update_cli_flag(opts, &mut cli_opts, &["overwrite"], "--overwrite")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["import"]);
+        }
+
+        let pipeline_name = get_string(opts, PIPELINE_NAME)?;
+        let command = self.command_string("import", pipeline_name.as_deref());
+        let subcommand = PipelineSubCommand::Import {
+            pipeline_name,
+            file: get_parsed(opts, &["file"])?,
+            format: get_parsed(opts, &["format"])?,
+            overwrite: get_flag(opts, &["overwrite"])?,
+        };
+        self.run_subcommand(subcommand, command)
     }
 
     #[pyo3(signature = (**opts))]
     fn step(&self, opts: Option<&Bound<PyDict>>) -> PyResult<XvcPipelineStep> {
+        check_opts(opts, &[])?;
         Ok(XvcPipelineStep {
             xvc_pipeline_opts: self.clone(),
         })
@@ -183,130 +291,222 @@ struct XvcPipelineStep {
 }
 
 impl XvcPipelineStep {
-    fn cli(&self) -> PyResult<Vec<String>> {
-        let mut cli_opts = self.xvc_pipeline_opts.cli()?;
-        cli_opts.push("step".to_string());
-        Ok(cli_opts)
+    fn help(&self, subcommand: &str) -> PyResult<String> {
+        self.xvc_pipeline_opts.help(&["step", subcommand])
     }
 
-    fn xvc_run(&self, args: Vec<String>) -> PyResult<String> {
-        self.xvc_pipeline_opts.xvc_run(args)
+    fn run_subcommand(
+        &self,
+        subcommand: StepSubCommand,
+        step_name: Option<&str>,
+    ) -> PyResult<String> {
+        let command = format!("step {}", step_subcommand_name(&subcommand));
+        let command = self.xvc_pipeline_opts.command_string(&command, step_name);
+        self.xvc_pipeline_opts
+            .run_subcommand(PipelineSubCommand::Step(StepCLI { subcommand }), command)
+    }
+}
+
+fn step_subcommand_name(subcommand: &StepSubCommand) -> &'static str {
+    match subcommand {
+        StepSubCommand::List { .. } => "list",
+        StepSubCommand::New { .. } => "new",
+        StepSubCommand::Update { .. } => "update",
+        StepSubCommand::Dependency { .. } => "dependency",
+        StepSubCommand::Output { .. } => "output",
+        StepSubCommand::Show { .. } => "show",
     }
 }
 
 #[pymethods]
 impl XvcPipelineStep {
-    #[pyo3(signature = (**opts))]
+    #[pyo3(signature = (options=None, **opts))]
     #[allow(clippy::wrong_self_convention)]
     #[allow(clippy::new_ret_no_self)]
-    fn new(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    fn new(
+        &self,
+        options: Option<&Bound<StepNewOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], STEP_NAME, &["command"], &["when"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help("new");
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "step_name", "step-name"],
-            "--step-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["command"], "--command")?;
-        update_cli_opt(opts, &mut cli_opts, &["when"], "--when")?;
-        self.xvc_run(cli_opts)
+        let step_name: String = get_required(opts, STEP_NAME)?;
+        let subcommand = StepSubCommand::New {
+            step_name: step_name.clone(),
+            command: get_required(opts, &["command"])?,
+            when: get_parsed(opts, &["when"])?,
+        };
+        self.run_subcommand(subcommand, Some(&step_name))
     }
 
-    #[pyo3(signature = (**opts))]
-    fn update(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("update".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn update(
+        &self,
+        options: Option<&Bound<StepUpdateOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], STEP_NAME, &["command"], &["when"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help("update");
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "step_name", "step-name"],
-            "--step-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["command"], "--command")?;
-        update_cli_opt(opts, &mut cli_opts, &["when"], "--when")?;
-        self.xvc_run(cli_opts)
+        let step_name: String = get_required(opts, STEP_NAME)?;
+        let subcommand = StepSubCommand::Update {
+            step_name: step_name.clone(),
+            command: get_string(opts, &["command"])?,
+            when: get_parsed(opts, &["when"])?,
+        };
+        self.run_subcommand(subcommand, Some(&step_name))
     }
 
-    #[pyo3(signature = (**opts))]
-    fn dependency(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("dependency".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        update_cli_flag(opts, &mut cli_opts, &["no_recheck"], "--no-recheck")?;
-
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn dependency(
+        &self,
+        options: Option<&Bound<StepDependencyOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["name", "step_name", "step-name"],
-            "--step-name",
+            &[
+                &["help"],
+                &["no_recheck"],
+                STEP_NAME,
+                &["file"],
+                &["url"],
+                &["glob"],
+                &["glob_items"],
+                &["step"],
+                &["param"],
+                &["regex"],
+                &["regex_items"],
+                &["line", "lines"],
+                &["line_items"],
+                &["generic"],
+                &["sqlite_file"],
+                &["sqlite_query"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["file"], "--file")?;
-        update_cli_opt(opts, &mut cli_opts, &["url"], "--url")?;
-        update_cli_opt(opts, &mut cli_opts, &["glob"], "--glob")?;
-        update_cli_opt(opts, &mut cli_opts, &["glob_items"], "--glob-items")?;
-        update_cli_opt(opts, &mut cli_opts, &["step"], "--step")?;
-        update_cli_opt(opts, &mut cli_opts, &["param"], "--param")?;
-        update_cli_opt(opts, &mut cli_opts, &["regex"], "--regex")?;
-        update_cli_opt(opts, &mut cli_opts, &["regex_items"], "--regex-items")?;
-        update_cli_opt(opts, &mut cli_opts, &["line", "lines"], "--line")?;
-        update_cli_opt(opts, &mut cli_opts, &["line_items"], "--line-items")?;
-        update_cli_opt(opts, &mut cli_opts, &["generic"], "--generic")?;
-        update_cli_tuple(
-            opts,
-            &mut cli_opts,
-            ("sqlite_file", "sqlite_query"),
-            "--sqlite-query",
-        )?;
-
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help("dependency");
+        }
+        no_recheck_unsupported(opts, "dependency")?;
+
+        let sqlite_query = match (
+            get_string(opts, &["sqlite_file"])?,
+            get_string(opts, &["sqlite_query"])?,
+        ) {
+            (Some(file), Some(query)) => Some(vec![file, query]),
+            _ => None,
+        };
+        let step_name: String = get_required(opts, STEP_NAME)?;
+        let subcommand = StepSubCommand::Dependency {
+            step_name: step_name.clone(),
+            generics: get_values(opts, &["generic"])?,
+            urls: get_values(opts, &["url"])?,
+            files: get_values(opts, &["file"])?,
+            steps: get_values(opts, &["step"])?,
+            glob_items: get_values(opts, &["glob_items"])?,
+            globs: get_values(opts, &["glob"])?,
+            params: get_values(opts, &["param"])?,
+            regex_items: get_values(opts, &["regex_items"])?,
+            regexes: get_values(opts, &["regex"])?,
+            line_items: get_values(opts, &["line_items"])?,
+            lines: get_values(opts, &["line", "lines"])?,
+            sqlite_query,
+        };
+        self.run_subcommand(subcommand, Some(&step_name))
     }
 
-    #[pyo3(signature = (**opts))]
-    fn output(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("output".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        update_cli_flag(opts, &mut cli_opts, &["no-recheck"], "--no-recheck")?;
-
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn output(
+        &self,
+        options: Option<&Bound<StepOutputOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["name", "step_name", "step-name"],
-            "--step-name",
+            &[
+                &["help"],
+                &["no-recheck", "no_recheck"],
+                STEP_NAME,
+                &["file"],
+                &["metric"],
+                &["image"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["file"], "--output-file")?;
-        update_cli_opt(opts, &mut cli_opts, &["metric"], "--output-metric")?;
-        update_cli_opt(opts, &mut cli_opts, &["image"], "--output-images")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help("output");
+        }
+        no_recheck_unsupported(opts, "output")?;
+
+        let step_name: String = get_required(opts, STEP_NAME)?;
+        let subcommand = StepSubCommand::Output {
+            step_name: step_name.clone(),
+            files: get_values(opts, &["file"])?,
+            metrics: get_values(opts, &["metric"])?,
+            images: get_values(opts, &["image"])?,
+        };
+        self.run_subcommand(subcommand, Some(&step_name))
     }
 
-    #[pyo3(signature = (**opts))]
-    fn list(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("list".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        update_cli_flag(opts, &mut cli_opts, &["names_only"], "--names-only")?;
+    #[pyo3(signature = (options=None, **opts))]
+    fn list(
+        &self,
+        options: Option<&Bound<StepListOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], &["names_only"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help("list");
+        }
 
-        self.xvc_run(cli_opts)
+        let subcommand = StepSubCommand::List {
+            names_only: get_flag(opts, &["names_only"])?,
+        };
+        self.run_subcommand(subcommand, None)
     }
 
-    #[pyo3(signature = (**opts))]
-    fn show(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-        cli_opts.push("show".to_string());
+    #[pyo3(signature = (options=None, **opts))]
+    fn show(
+        &self,
+        options: Option<&Bound<StepShowOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], STEP_NAME])?;
+        if get_flag(opts, &["help"])? {
+            return self.help("show");
+        }
 
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["name", "step_name", "step-name"],
-            "--step-name",
-        )?;
-        self.xvc_run(cli_opts)
+        let step_name: String = get_required(opts, STEP_NAME)?;
+        let subcommand = StepSubCommand::Show {
+            step_name: step_name.clone(),
+        };
+        self.run_subcommand(subcommand, Some(&step_name))
+    }
+}
+
+/// `no_recheck` has no equivalent in the options of the step commands
+fn no_recheck_unsupported(opts: Option<&Bound<PyDict>>, subcommand: &str) -> PyResult<()> {
+    if get_flag(opts, &["no-recheck", "no_recheck"])? {
+        return Err(PyValueError::new_err(format!(
+            "'no_recheck' is not supported by 'step {}'",
+            subcommand
+        )));
     }
+    Ok(())
 }
@@ -0,0 +1,313 @@
+//! Introspection of the Xvc command line definition.
+//!
+//! The command tree is read from the clap definition of [XvcCLI], so options added to Xvc are
+//! available here without changes in the bindings.
+
+use std::collections::HashSet;
+
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyTuple};
+use xvc_rust::cli::XvcCLI;
+
+use crate::{check_opts, get_bool, suggest_key, update_cli_flag, update_cli_opt, update_targets};
+
+/// An option or positional argument of a command
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct OptionInfo {
+    /// The keyword argument name
+    pub name: String,
+    /// Other accepted keyword argument names
+    pub aliases: Vec<String>,
+    pub long: Option<String>,
+    pub short: Option<char>,
+    /// One of `bool`, `count`, `choice` or `str`
+    pub value_type: String,
+    /// Whether the option can be given multiple values
+    pub multiple: bool,
+    pub positional: bool,
+    pub required: bool,
+    pub default: Vec<String>,
+    pub choices: Vec<String>,
+    pub help: Option<String>,
+}
+
+#[pymethods]
+impl OptionInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "OptionInfo(name={:?}, value_type={:?}, default={:?})",
+            self.name, self.value_type, self.default
+        )
+    }
+}
+
+/// A command in the Xvc command tree
+#[pyclass(get_all, module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct CommandInfo {
+    /// Subcommand names from the root, e.g. `["file", "track"]`
+    pub path: Vec<String>,
+    pub about: Option<String>,
+    pub options: Vec<OptionInfo>,
+    /// Names of the direct subcommands
+    pub subcommands: Vec<String>,
+}
+
+#[pymethods]
+impl CommandInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "CommandInfo(path={:?}, options={})",
+            self.path,
+            self.options.len()
+        )
+    }
+}
+
+/// Arguments that are handled by clap itself
+fn is_builtin(arg: &Arg) -> bool {
+    matches!(
+        arg.get_action(),
+        ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version
+    )
+}
+
+fn option_info(arg: &Arg) -> OptionInfo {
+    let name = arg.get_id().as_str().replace('-', "_");
+    let mut aliases = Vec::new();
+    if let Some(long) = arg.get_long() {
+        if long != name {
+            aliases.push(long.to_string());
+        }
+        let underscored = long.replace('-', "_");
+        if underscored != name && underscored != long {
+            aliases.push(underscored);
+        }
+    }
+    for alias in arg.get_all_aliases().unwrap_or_default() {
+        aliases.push(alias.to_string());
+        if alias.contains('-') {
+            aliases.push(alias.replace('-', "_"));
+        }
+    }
+
+    let choices: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+
+    let value_type = match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => "bool",
+        ArgAction::Count => "count",
+        _ if !choices.is_empty() => "choice",
+        _ => "str",
+    };
+
+    let multiple = matches!(arg.get_action(), ArgAction::Append)
+        || arg
+            .get_num_args()
+            .map(|range| range.max_values() > 1)
+            .unwrap_or(false);
+
+    let default = if value_type == "bool" || value_type == "count" {
+        Vec::new()
+    } else {
+        arg.get_default_values()
+            .iter()
+            .map(|v| v.to_string_lossy().to_string())
+            .collect()
+    };
+
+    OptionInfo {
+        name,
+        aliases,
+        long: arg.get_long().map(|l| l.to_string()),
+        short: arg.get_short(),
+        value_type: value_type.to_string(),
+        multiple,
+        positional: arg.is_positional(),
+        required: arg.is_required_set(),
+        default,
+        choices,
+        help: arg.get_help().map(|h| h.to_string()),
+    }
+}
+
+fn collect_commands(command: &Command, path: Vec<String>, commands: &mut Vec<CommandInfo>) {
+    let subcommands: Vec<&Command> = command
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+        .collect();
+
+    commands.push(CommandInfo {
+        path: path.clone(),
+        about: command.get_about().map(|a| a.to_string()),
+        options: command
+            .get_arguments()
+            .filter(|a| !is_builtin(a) && !a.is_hide_set())
+            .map(option_info)
+            .collect(),
+        subcommands: subcommands
+            .iter()
+            .map(|c| c.get_name().to_string())
+            .collect(),
+    });
+
+    for subcommand in subcommands {
+        let mut subpath = path.clone();
+        subpath.push(subcommand.get_name().to_string());
+        collect_commands(subcommand, subpath, commands);
+    }
+}
+
+/// All commands of Xvc, starting from the root command
+pub fn command_infos() -> Vec<CommandInfo> {
+    let mut commands = Vec::new();
+    collect_commands(&XvcCLI::command(), Vec::new(), &mut commands);
+    commands
+}
+
+/// Find the subcommand `name` of `command`, raising `ValueError` with a suggestion for unknown
+/// names
+fn find_subcommand<'a>(command: &'a Command, name: &str) -> PyResult<&'a Command> {
+    match command
+        .get_subcommands()
+        .find(|c| c.get_name() == name || c.get_all_aliases().any(|a| a == name))
+    {
+        Some(c) => Ok(c),
+        None => {
+            let names: Vec<&str> = command.get_subcommands().map(|c| c.get_name()).collect();
+            let groups: Vec<&[&str]> = names.iter().map(std::slice::from_ref).collect();
+            let message = match suggest_key(name, &groups) {
+                Some(s) => format!("unknown command '{}'. Did you mean '{}'?", name, s),
+                None => format!("unknown command '{}'", name),
+            };
+            Err(PyValueError::new_err(message))
+        }
+    }
+}
+
+/// The flag to pass an option on the command line
+fn cli_flag(arg: &Arg) -> String {
+    match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => format!("--{}", long),
+        (None, Some(short)) => format!("-{}", short),
+        (None, None) => format!("--{}", arg.get_id()),
+    }
+}
+
+/// The number of times to repeat a count option like `verbose`. `True` counts as one.
+fn get_count(dict: Option<&Bound<PyDict>>, keys: &[&str]) -> PyResult<Option<usize>> {
+    if let Some(dict) = dict {
+        for key in keys {
+            if let Some(value) = dict.get_item(key)? {
+                if value.is_none() {
+                    return Ok(None);
+                }
+                if let Ok(b) = value.downcast::<PyBool>() {
+                    return Ok(Some(usize::from(b.is_true())));
+                }
+                return value.extract::<usize>().map(Some).map_err(|_| {
+                    PyTypeError::new_err(format!("'{}' expects an int or a bool", key))
+                });
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Build the command line arguments of the subcommand in `path` from keyword arguments.
+///
+/// Options of the intermediate commands in `path`, like `pipeline_name` for `["pipeline",
+/// "step", "new"]`, are accepted too. When a name is an option of more than one command in
+/// `path`, it's passed to the last one only. The keyword arguments are validated against these
+/// options with [check_opts]. Positional arguments are passed after `--`. Options of the root
+/// command come from the [crate::Xvc] object and aren't accepted here.
+pub fn command_args(
+    path: &[String],
+    targets: &Bound<PyTuple>,
+    opts: Option<&Bound<PyDict>>,
+) -> PyResult<Vec<String>> {
+    let root = XvcCLI::command();
+    let mut commands = Vec::<&Command>::new();
+    let mut command = &root;
+    for name in path {
+        command = find_subcommand(command, name)?;
+        commands.push(command);
+    }
+    if commands.is_empty() || command.get_subcommands().any(|c| c.get_name() != "help") {
+        return Err(PyValueError::new_err(format!(
+            "'{}' requires a subcommand",
+            path.join(" ")
+        )));
+    }
+
+    // (level in path, option, keys). Deeper levels come first so that they take the keys they
+    // share with the upper levels.
+    let mut options: Vec<(usize, &Arg, Vec<String>)> = Vec::new();
+    let mut seen_keys = HashSet::<String>::new();
+    for (level, command) in commands.iter().copied().enumerate().rev() {
+        for arg in command
+            .get_arguments()
+            .filter(|a| !is_builtin(a) && !a.is_positional())
+        {
+            let info = option_info(arg);
+            let keys: Vec<String> = std::iter::once(info.name)
+                .chain(info.aliases)
+                .filter(|k| !seen_keys.contains(k))
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            seen_keys.extend(keys.iter().cloned());
+            options.push((level, arg, keys));
+        }
+    }
+    options.reverse();
+
+    let keys: Vec<Vec<&str>> = options
+        .iter()
+        .map(|(_, _, keys)| keys.iter().map(|k| k.as_str()).collect())
+        .collect();
+    let mut groups: Vec<&[&str]> = keys.iter().map(|k| k.as_slice()).collect();
+    groups.push(&["help"]);
+    check_opts(opts, &groups)?;
+
+    let mut args = Vec::new();
+    for (level, name) in path.iter().enumerate() {
+        args.push(name.clone());
+        for ((option_level, arg, _), keys) in options.iter().zip(keys.iter()) {
+            if *option_level != level {
+                continue;
+            }
+            let flag = cli_flag(arg);
+            match arg.get_action() {
+                ArgAction::SetTrue => update_cli_flag(opts, &mut args, keys, &flag)?,
+                ArgAction::SetFalse => {
+                    if get_bool(opts, keys)? == Some(false) {
+                        args.push(flag);
+                    }
+                }
+                ArgAction::Count => {
+                    if let Some(count) = get_count(opts, keys)? {
+                        args.extend(std::iter::repeat(flag).take(count));
+                    }
+                }
+                _ => update_cli_opt(opts, &mut args, keys, &flag)?,
+            }
+        }
+    }
+    update_cli_flag(opts, &mut args, &["help"], "--help")?;
+
+    if !targets.is_empty() {
+        args.push("--".to_string());
+        update_targets(targets, &mut args)?;
+    }
+
+    Ok(args)
+}
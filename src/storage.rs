@@ -1,9 +1,21 @@
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
+use xvc_rust::storage::{StorageCLI, StorageNewSubCommand, StorageSubCommand};
 use xvc_rust::watch;
 
+use crate::options::{
+    merge_options, DigitalOceanStorageOptions, GcsStorageOptions, GenericStorageOptions,
+    LocalStorageOptions, MinioStorageOptions, R2StorageOptions, RcloneStorageOptions,
+    RsyncStorageOptions, S3StorageOptions, WasabiStorageOptions,
+};
+use crate::output::dispatch_storage;
 use crate::Xvc;
-use crate::{update_cli_flag, update_cli_opt};
+use crate::{check_opts, get_flag, get_parsed, get_required, get_string};
+
+/// Keys of the storage prefix option of cloud storages
+const STORAGE_PREFIX: &[&str] = &["storage_prefix", "storage-prefix"];
+/// Keys of the bucket name option of cloud storages
+const BUCKET_NAME: &[&str] = &["bucket_name", "bucket-name"];
 
 #[pyclass]
 #[derive(Clone, Debug)]
@@ -24,8 +36,31 @@ impl XvcStorage {
         Ok(cli_opts)
     }
 
-    fn xvc_run(&self, args: Vec<String>) -> PyResult<String> {
-        self.xvc_opts.run(args)
+    /// Show the help text of a subcommand
+    fn help(&self, subcommand: &[&str]) -> PyResult<String> {
+        let mut cli_opts = self.cli()?;
+        cli_opts.extend(subcommand.iter().map(|s| s.to_string()));
+        cli_opts.push("--help".to_string());
+        self.xvc_opts.run(cli_opts)
+    }
+
+    /// Run the subcommand with the API, without building a command line
+    fn run(&self, subcommand: StorageSubCommand, command_string: String) -> PyResult<String> {
+        watch!(subcommand);
+        let out = dispatch_storage(
+            &self.xvc_opts.xvc_root_opt,
+            &self.xvc_opts.context(command_string),
+            StorageCLI { subcommand },
+        )?;
+        Ok(out.output)
+    }
+
+    /// Add a storage. `kind` is the subcommand of `storage new` used in the commit message.
+    fn run_new(&self, kind: &str, subcommand: StorageNewSubCommand) -> PyResult<String> {
+        self.run(
+            StorageSubCommand::New(subcommand),
+            format!("xvc storage new {}", kind),
+        )
     }
 }
 
@@ -33,282 +68,354 @@ impl XvcStorage {
 impl XvcStorage {
     #[pyo3(signature = (**opts))]
     fn list(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("list".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
+        check_opts(opts, &[&["help"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["list"]);
+        }
 
-        self.xvc_run(cli_opts)
+        self.run(StorageSubCommand::List, "xvc storage list".to_string())
     }
 
     #[pyo3(signature = (name, **opts))]
     fn remove(&self, name: &str, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("remove".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        cli_opts.push("--name".to_string());
-        cli_opts.push(name.to_string());
-
-        self.xvc_run(cli_opts)
+        check_opts(opts, &[&["help"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["remove"]);
+        }
+
+        self.run(
+            StorageSubCommand::Remove {
+                name: name.to_string(),
+            },
+            format!("xvc storage remove --name {}", name),
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_local(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("local".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(opts, &mut cli_opts, &["path"], "--path")?;
-
-        self.xvc_run(cli_opts)
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_local(
+        &self,
+        options: Option<&Bound<LocalStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], &["name"], &["path"]])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "local"]);
+        }
+
+        self.run_new(
+            "local",
+            StorageNewSubCommand::Local {
+                name: get_required(opts, &["name"])?,
+                path: get_required(opts, &["path"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_generic(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("generic".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_generic(
+        &self,
+        options: Option<&Bound<GenericStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["init", "init_command", "init-command"],
-            "--init",
+            &[
+                &["help"],
+                &["name"],
+                &["init", "init_command", "init-command"],
+                &["list", "list_command", "list-command"],
+                &["download", "download_command", "download-command"],
+                &["upload", "upload_command", "upload-command"],
+                &["delete", "delete_command", "delete-command"],
+                &["processes", "max_processes", "max-processes"],
+                &["url"],
+                &["storage_dir", "storage-dir"],
+            ],
         )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["list", "list_command", "list-command"],
-            "--list",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["download", "download_command", "download-command"],
-            "--download",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["upload", "upload_command", "upload-command"],
-            "--upload",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["delete", "delete_command", "delete-command"],
-            "--delete",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["processes", "max_processes", "max-processes"],
-            "--processes",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["url"], "--url")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_dir", "storage-dir"],
-            "--storage-dir",
-        )?;
-
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "generic"]);
+        }
+
+        self.run_new(
+            "generic",
+            StorageNewSubCommand::Generic {
+                name: get_required(opts, &["name"])?,
+                init: get_required(opts, &["init", "init_command", "init-command"])?,
+                list: get_required(opts, &["list", "list_command", "list-command"])?,
+                download: get_required(
+                    opts,
+                    &["download", "download_command", "download-command"],
+                )?,
+                upload: get_required(opts, &["upload", "upload_command", "upload-command"])?,
+                delete: get_required(opts, &["delete", "delete_command", "delete-command"])?,
+                processes: get_parsed(opts, &["processes", "max_processes", "max-processes"])?
+                    .unwrap_or(1),
+                url: get_string(opts, &["url"])?,
+                storage_dir: get_string(opts, &["storage_dir", "storage-dir"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_rsync(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("rsync".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(opts, &mut cli_opts, &["host"], "--host")?;
-        update_cli_opt(opts, &mut cli_opts, &["port"], "--port")?;
-        update_cli_opt(opts, &mut cli_opts, &["user"], "--user")?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_rsync(
+        &self,
+        options: Option<&Bound<RsyncStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["storage_dir", "storage-dir"],
-            "--storage-dir",
+            &[
+                &["help"],
+                &["name"],
+                &["host"],
+                &["port"],
+                &["user"],
+                &["storage_dir", "storage-dir"],
+            ],
         )?;
-
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "rsync"]);
+        }
+
+        self.run_new(
+            "rsync",
+            StorageNewSubCommand::Rsync {
+                name: get_required(opts, &["name"])?,
+                host: get_required(opts, &["host"])?,
+                port: get_parsed(opts, &["port"])?,
+                user: get_string(opts, &["user"])?,
+                storage_dir: get_required(opts, &["storage_dir", "storage-dir"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_rclone(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("rclone".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(opts, &mut cli_opts, &["remote"], "--remote")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
-        )?;
-
-        self.xvc_run(cli_opts)
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_rclone(
+        &self,
+        options: Option<&Bound<RcloneStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(opts, &[&["help"], &["name"], &["remote"], STORAGE_PREFIX])?;
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "rclone"]);
+        }
+
+        self.run_new(
+            "rclone",
+            StorageNewSubCommand::Rclone {
+                name: get_required(opts, &["name"])?,
+                remote: get_required(opts, &["remote"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_s3(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        watch!(cli_opts);
-        cli_opts.push("new".to_string());
-        cli_opts.push("s3".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
-        )?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_s3(
+        &self,
+        options: Option<&Bound<S3StorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                BUCKET_NAME,
+                &["region"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["region"], "--region")?;
-        watch!(cli_opts);
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "s3"]);
+        }
+
+        self.run_new(
+            "s3",
+            StorageNewSubCommand::S3 {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+                region: get_required(opts, &["region"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_minio(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("minio".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
-        )?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_minio(
+        &self,
+        options: Option<&Bound<MinioStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                BUCKET_NAME,
+                &["endpoint"],
+                &["region"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["endpoint"], "--endpoint")?;
-        update_cli_opt(opts, &mut cli_opts, &["region"], "--region")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "minio"]);
+        }
+
+        self.run_new(
+            "minio",
+            StorageNewSubCommand::Minio {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+                endpoint: get_required(opts, &["endpoint"])?,
+                region: get_required(opts, &["region"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_digital_ocean(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("digital-ocean".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
-        )?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_digital_ocean(
+        &self,
+        options: Option<&Bound<DigitalOceanStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                BUCKET_NAME,
+                &["region"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["region"], "--region")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "digital-ocean"]);
+        }
+
+        self.run_new(
+            "digital-ocean",
+            StorageNewSubCommand::DigitalOcean {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+                region: get_required(opts, &["region"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_r2(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("r2".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_r2(
+        &self,
+        options: Option<&Bound<R2StorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                &["account_id", "account-id"],
+                BUCKET_NAME,
+            ],
         )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["account_id", "account-id"],
-            "--account-id",
-        )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
-        )?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "r2"]);
+        }
+
+        self.run_new(
+            "r2",
+            StorageNewSubCommand::R2 {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                account_id: get_required(opts, &["account_id", "account-id"])?,
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_gcs(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("gcs".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_gcs(
+        &self,
+        options: Option<&Bound<GcsStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                BUCKET_NAME,
+                &["region"],
+            ],
         )?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
-        )?;
-        update_cli_opt(opts, &mut cli_opts, &["region"], "--region")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "gcs"]);
+        }
+
+        self.run_new(
+            "gcs",
+            StorageNewSubCommand::Gcs {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+                region: get_required(opts, &["region"])?,
+            },
+        )
     }
 
-    #[pyo3(signature = (**opts))]
-    fn new_wasabi(&self, opts: Option<&Bound<PyDict>>) -> PyResult<String> {
-        let mut cli_opts = self.cli()?;
-        cli_opts.push("new".to_string());
-        cli_opts.push("wasabi".to_string());
-        update_cli_flag(opts, &mut cli_opts, &["help"], "--help")?;
-
-        update_cli_opt(opts, &mut cli_opts, &["name"], "--name")?;
-        update_cli_opt(
-            opts,
-            &mut cli_opts,
-            &["storage_prefix", "storage-prefix"],
-            "--storage-prefix",
-        )?;
-        update_cli_opt(
+    #[pyo3(signature = (options=None, **opts))]
+    fn new_wasabi(
+        &self,
+        options: Option<&Bound<WasabiStorageOptions>>,
+        opts: Option<&Bound<PyDict>>,
+    ) -> PyResult<String> {
+        let opts = merge_options(options, opts)?;
+        let opts = opts.as_ref();
+        check_opts(
             opts,
-            &mut cli_opts,
-            &["bucket_name", "bucket-name"],
-            "--bucket-name",
+            &[
+                &["help"],
+                &["name"],
+                STORAGE_PREFIX,
+                BUCKET_NAME,
+                &["endpoint"],
+            ],
         )?;
-        update_cli_opt(opts, &mut cli_opts, &["endpoint"], "--endpoint")?;
-        self.xvc_run(cli_opts)
+        if get_flag(opts, &["help"])? {
+            return self.help(&["new", "wasabi"]);
+        }
+
+        self.run_new(
+            "wasabi",
+            StorageNewSubCommand::Wasabi {
+                name: get_required(opts, &["name"])?,
+                storage_prefix: get_string(opts, STORAGE_PREFIX)?.unwrap_or_default(),
+                bucket_name: get_required(opts, BUCKET_NAME)?,
+                endpoint: get_string(opts, &["endpoint"])?
+                    .unwrap_or_else(|| "s3.wasabisys.com".to_string()),
+            },
+        )
     }
 }
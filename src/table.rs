@@ -0,0 +1,25 @@
+//! Conversion of columnar results to DataFrame libraries.
+//!
+//! The libraries are imported when a conversion is requested, so none of them is a dependency of
+//! the bindings.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Build a `pandas.DataFrame` from a dict of column name to values
+pub fn to_pandas<'py>(columns: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyAny>> {
+    let pandas = columns.py().import("pandas")?;
+    pandas.getattr("DataFrame")?.call1((columns,))
+}
+
+/// Build a `polars.DataFrame` from a dict of column name to values
+pub fn to_polars<'py>(columns: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyAny>> {
+    let polars = columns.py().import("polars")?;
+    polars.getattr("DataFrame")?.call1((columns,))
+}
+
+/// Build a `pyarrow.Table` from a dict of column name to values
+pub fn to_arrow<'py>(columns: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyAny>> {
+    let pyarrow = columns.py().import("pyarrow")?;
+    pyarrow.getattr("table")?.call1((columns,))
+}
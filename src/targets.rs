@@ -0,0 +1,152 @@
+//! Targets read from Python iterables and manifest files.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyIterator, PyTuple};
+
+use crate::{is_target_iterable, to_cli_values};
+
+/// Number of targets passed to a command at once
+pub const TARGET_CHUNK_SIZE: usize = 10_000;
+
+/// Number of targets listed in command strings, to keep commit messages short with many targets
+pub const COMMAND_STRING_TARGETS: usize = 20;
+
+/// A file listing targets, one path per line, passed in place of targets.
+///
+/// Empty lines and lines starting with `#` are skipped. Paths are relative to the current
+/// directory, like other targets. The file is read line by line when the command is called.
+#[pyclass(module = "xvc")]
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    #[pyo3(get)]
+    path: PathBuf,
+}
+
+#[pymethods]
+impl Manifest {
+    #[new]
+    fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Manifest({:?})", self.path.to_string_lossy())
+    }
+}
+
+impl Manifest {
+    fn lines(&self) -> PyResult<Lines<BufReader<File>>> {
+        Ok(BufReader::new(File::open(&self.path)?).lines())
+    }
+}
+
+/// The target in a manifest `line`, if it isn't empty or a comment
+fn manifest_target(line: &str) -> Option<String> {
+    let target = line.trim_end_matches('\r');
+    if target.trim().is_empty() || target.starts_with('#') {
+        None
+    } else {
+        Some(target.to_string())
+    }
+}
+
+/// Targets of a command, read in chunks of [TARGET_CHUNK_SIZE] from the positional arguments.
+///
+/// An argument can be a target, a [Manifest] or an iterable of them. Iterables and manifests are
+/// read only as far as the current chunk needs.
+pub struct TargetChunks<'py> {
+    args: Bound<'py, PyTuple>,
+    index: usize,
+    iterable: Option<Bound<'py, PyIterator>>,
+    manifest: Option<Lines<BufReader<File>>>,
+    count: usize,
+    listed: Vec<String>,
+}
+
+impl<'py> TargetChunks<'py> {
+    pub fn new(args: &Bound<'py, PyTuple>) -> Self {
+        Self {
+            args: args.clone(),
+            index: 0,
+            iterable: None,
+            manifest: None,
+            count: 0,
+            listed: Vec::new(),
+        }
+    }
+
+    /// The next targets, or `None` when all are read
+    pub fn next_chunk(&mut self) -> PyResult<Option<Vec<String>>> {
+        let mut chunk = Vec::new();
+        while chunk.len() < TARGET_CHUNK_SIZE && self.read_next(&mut chunk)? {}
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+        let unlisted = COMMAND_STRING_TARGETS.saturating_sub(self.listed.len());
+        self.listed.extend(chunk.iter().take(unlisted).cloned());
+        self.count += chunk.len();
+        Ok(Some(chunk))
+    }
+
+    /// Number of targets read so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The first [COMMAND_STRING_TARGETS] targets read
+    pub fn listed(&self) -> &[String] {
+        &self.listed
+    }
+
+    /// Read the next argument, iterable item or manifest line to `chunk`. Returns false when
+    /// there is nothing left.
+    fn read_next(&mut self, chunk: &mut Vec<String>) -> PyResult<bool> {
+        if let Some(lines) = &mut self.manifest {
+            match lines.next() {
+                Some(line) => chunk.extend(manifest_target(&line?)),
+                None => self.manifest = None,
+            }
+            return Ok(true);
+        }
+        if let Some(items) = &mut self.iterable {
+            match items.next() {
+                Some(item) => self.read_item(&item?, chunk, true)?,
+                None => self.iterable = None,
+            }
+            return Ok(true);
+        }
+        if self.index < self.args.len() {
+            let arg = self.args.get_item(self.index)?;
+            self.index += 1;
+            self.read_item(&arg, chunk, false)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn read_item(
+        &mut self,
+        item: &Bound<'py, PyAny>,
+        chunk: &mut Vec<String>,
+        nested: bool,
+    ) -> PyResult<()> {
+        if let Ok(manifest) = item.downcast::<Manifest>() {
+            self.manifest = Some(manifest.borrow().lines()?);
+        } else if is_target_iterable(item)? {
+            if nested {
+                return Err(PyTypeError::new_err(
+                    "'targets' doesn't accept nested iterables",
+                ));
+            }
+            self.iterable = Some(item.try_iter()?);
+        } else {
+            to_cli_values(item, "targets", chunk)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+import os
+import pytest
+
+
+def test_commands_lists_file_track(empty_xvc_repo):
+    commands = {tuple(c.path): c for c in empty_xvc_repo.commands()}
+    assert ("file",) in commands
+    track = commands[("file", "track")]
+    names = {o.name for o in track.options}
+    assert "recheck_method" in names
+    assert "no_commit" in names
+    recheck_method = next(o for o in track.options if o.name == "recheck_method")
+    assert "symlink" in recheck_method.choices
+
+
+def test_call_file_track(xvc_repo_with_dir):
+    xvc_repo_with_dir.call(
+        ["file", "track"], "dir-0001/file-0001.bin", recheck_method="symlink"
+    )
+    assert os.path.islink("dir-0001/file-0001.bin")
+
+
+def test_call_pipeline_step_new(empty_xvc_repo):
+    empty_xvc_repo.call(
+        ["pipeline", "step", "new"], step_name="hello", command="echo hello"
+    )
+    assert "hello" in empty_xvc_repo.pipeline().step().list()
+
+
+def test_call_unknown_option(xvc_repo_with_dir):
+    with pytest.raises(TypeError, match="Did you mean 'recheck_method'"):
+        xvc_repo_with_dir.call(["file", "track"], recheck_methd="symlink")
+
+
+def test_call_unknown_command(xvc_repo_with_dir):
+    with pytest.raises(ValueError, match="Did you mean 'track'"):
+        xvc_repo_with_dir.call(["file", "trak"])
+
+
+def test_call_option_of_multiple_levels(empty_xvc_repo):
+    empty_xvc_repo.call(
+        ["pipeline", "step", "new"],
+        pipeline_name="default",
+        step_name="hello",
+        command="echo hello",
+    )
+    assert "hello" in empty_xvc_repo.pipeline().step().list()
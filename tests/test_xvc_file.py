@@ -1,15 +1,75 @@
+import csv
+import datetime
+import functools
+import gc
+import http.server
+import json
 import os
+import pathlib
+import pytest
 import shutil
+import subprocess
+import tempfile
+import threading
+
+from xvc import (
+    BringOptions,
+    HashAlgorithm,
+    ListFormat,
+    ListOptions,
+    ListSortCriteria,
+    Manifest,
+    RecheckMethod,
+    TrackOptions,
+    Xvc,
+)
 
 
 def test_file_hash(xvc_repo_with_dir):
     print(xvc_repo_with_dir.root(absolute=True))
-    hash_string = xvc_repo_with_dir.file().hash("dir-0001/file-0002.bin")
+    hash_string = str(xvc_repo_with_dir.file().hash("dir-0001/file-0002.bin"))
     assert hash_string.startswith(
         "6432c99dec9e4a6c208ab78cfb58749ece5090fa8e279e6fd5a8cfd431e053f5"
     )
 
 
+def test_file_hash_mapping(xvc_repo_with_dir):
+    digests = xvc_repo_with_dir.file().hash("dir-0001/")
+    assert len(digests) == 3
+    digest = digests["dir-0001/file-0002.bin"]
+    assert digest.hex == (
+        "6432c99dec9e4a6c208ab78cfb58749ece5090fa8e279e6fd5a8cfd431e053f5"
+    )
+    assert digest.bytes == bytes.fromhex(digest.hex)
+    assert pathlib.Path("dir-0001/file-0002.bin") in digests
+
+
+def test_file_hash_algorithm(xvc_repo_with_dir):
+    blake3 = xvc_repo_with_dir.file().hash("dir-0001/file-0002.bin")
+    sha2 = xvc_repo_with_dir.file().hash(
+        "dir-0001/file-0002.bin", algorithm=HashAlgorithm.Sha2
+    )
+    path = "dir-0001/file-0002.bin"
+    assert sha2[path].algorithm == "sha2"
+    assert sha2[path] != blake3[path]
+
+
+def test_file_hash_data(xvc_repo_with_dir):
+    path = "dir-0001/file-0002.bin"
+    digest = xvc_repo_with_dir.file().hash(path)[path]
+    with open(path, "rb") as f:
+        content = f.read()
+        f.seek(0)
+        assert xvc_repo_with_dir.file().hash_data(f) == digest
+    assert xvc_repo_with_dir.file().hash_data(content) == digest.hex
+    assert xvc_repo_with_dir.file().hash_data(bytearray(content)).bytes == digest.bytes
+    assert xvc_repo_with_dir.file().hash_data(content).algorithm == digest.algorithm
+    assert len({digest, digest.hex, xvc_repo_with_dir.file().hash_data(content)}) == 1
+
+    with pytest.raises(TypeError, match="encode str"):
+        xvc_repo_with_dir.file().hash_data("text")
+
+
 def test_file_track_symlink(xvc_repo_with_dir):
     assert ".git" in os.listdir()
     assert ".xvc" in os.listdir()
@@ -59,15 +119,17 @@ def test_file_move(xvc_repo_with_dir):
 
 
 def test_file_list(xvc_repo_with_dir):
-    file_list = xvc_repo_with_dir.file().list().split("\n")
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
     assert len([line for line in file_list if line.startswith("FX")]) == 9
 
     xvc_repo_with_dir.file().track("dir-0001/")
-    file_list = xvc_repo_with_dir.file().list().split("\n")
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
     assert len([line for line in file_list if line.startswith("FC")]) == 3
     assert len([line for line in file_list if line.startswith("FX")]) == 6
 
-    file_list = xvc_repo_with_dir.file().list(show_directories=True).split("\n")
+    file_list = str(xvc_repo_with_dir.file().list(show_directories=True)).split(
+        "\n"
+    )
     assert len([line for line in file_list if line.startswith("DX")]) == 3
 
 
@@ -79,18 +141,785 @@ def test_file_remove(xvc_repo_with_dir):
     assert len(os.listdir(".xvc/b3/")) == 2
 
 
+def test_file_remove_dry_run(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/")
+    size = os.path.getsize("dir-0001/file-0001.bin")
+
+    plan = xvc_repo_with_dir.file().remove(
+        "dir-0001/file-0001.bin", from_cache=True, dry_run=True
+    )
+    assert plan.dry_run
+    assert [f.path for f in plan.removed] == ["dir-0001/file-0001.bin"]
+    assert plan.removed[0].location == "cache"
+    assert plan.reclaimed_bytes == size
+    assert len(os.listdir(".xvc/b3/")) == 3
+
+    report = xvc_repo_with_dir.file().remove("dir-0001/file-0001.bin", from_cache=True)
+    assert not report.dry_run
+    assert [f.path for f in report.removed] == ["dir-0001/file-0001.bin"]
+    assert report.reclaimed_bytes == size
+    assert report.failures == []
+    assert len(os.listdir(".xvc/b3/")) == 2
+
+
+def test_file_remove_dry_run_matches_removal(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().send("dir-0001/", storage="local")
+
+    first_digests = {}
+    for path in ["dir-0001/file-0001.bin", "dir-0001/file-0002.bin"]:
+        first_digests[path] = xvc_repo_with_dir.file().hash(path)[path].hex
+        os.chmod(path, 0o644)
+        pathlib.Path(path).write_bytes(b"second version of " + path.encode())
+        xvc_repo_with_dir.file().carry_in(path)
+        xvc_repo_with_dir.file().send(path, storage="local")
+
+    cases = [
+        (
+            "dir-0001/file-0001.bin",
+            dict(from_cache=True, from_storage="local", all_versions=True),
+        ),
+        (
+            "dir-0001/file-0002.bin",
+            dict(
+                from_cache=True,
+                only_version=first_digests["dir-0001/file-0002.bin"][:8],
+            ),
+        ),
+        ("dir-0001/file-0003.bin", dict(from_storage="local")),
+    ]
+    for path, options in cases:
+        plan = xvc_repo_with_dir.file().remove(path, dry_run=True, **options)
+        report = xvc_repo_with_dir.file().remove(path, **options)
+        assert report.failures == []
+        assert plan.removed
+        assert sorted((f.location, f.digest) for f in plan.removed) == sorted(
+            (f.location, f.digest) for f in report.removed
+        )
+        assert plan.reclaimed_bytes == report.reclaimed_bytes
+
+
 def test_file_untrack(xvc_repo_with_dir):
     xvc_repo_with_dir.file().track("dir-0001/")
     assert len(os.listdir(".xvc/b3/")) == 3
 
-    file_list = xvc_repo_with_dir.file().list().split("\n")
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
     assert len([line for line in file_list if line.startswith("FX")]) == 6
     assert len([line for line in file_list if line.startswith("FC")]) == 3
 
-    xvc_repo_with_dir.file().untrack("dir-0001/file-0001.bin")
+    xvc_repo_with_dir.file().untrack("dir-0001/file-0001.bin", remove_cache=True)
 
-    file_list = xvc_repo_with_dir.file().list().split("\n")
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
     assert len([line for line in file_list if line.startswith("FX")]) == 7
     assert len([line for line in file_list if line.startswith("FC")]) == 2
 
     assert len(os.listdir(".xvc/b3/")) == 2
+
+
+def test_file_untrack_restore_versions(xvc_repo_with_dir):
+    path = "dir-0001/file-0001.bin"
+    xvc_repo_with_dir.file().track(path)
+    first = pathlib.Path(path).read_bytes()
+    os.chmod(path, 0o644)
+    pathlib.Path(path).write_bytes(b"second version")
+    xvc_repo_with_dir.file().carry_in(path)
+
+    restore_dir = tempfile.mkdtemp()
+    restored = xvc_repo_with_dir.file().untrack(path, restore_dir=restore_dir)
+    assert sorted(pathlib.Path(r).read_bytes() for r in restored) == sorted(
+        [first, b"second version"]
+    )
+    assert all(r.restored_path.endswith(".bin") for r in restored)
+    assert len(os.listdir(".xvc/b3/")) == 2
+    assert not [d for d in os.listdir(".xvc") if d.startswith("untrack-backup")]
+
+    assert xvc_repo_with_dir.file().untrack("dir-0001/file-0002.bin") == []
+
+
+def test_file_unknown_option(xvc_repo_with_dir):
+    with pytest.raises(TypeError, match="Did you mean 'recheck_method'"):
+        xvc_repo_with_dir.file().track("dir-0001/", recheck_methd="symlink")
+    assert not os.path.exists(".xvc/b3/")
+
+
+def test_file_conflicting_aliases(xvc_repo_with_dir):
+    with pytest.raises(TypeError, match="aliases of the same option"):
+        xvc_repo_with_dir.file().list(show_dirs=True, show_directories=True)
+
+
+def test_file_track_pathlike(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track(
+        pathlib.Path("dir-0001") / "file-0001.bin", recheck_method="symlink"
+    )
+    assert os.path.islink("dir-0001/file-0001.bin")
+
+
+def test_file_track_target_list(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track(["dir-0001/file-0001.bin", "dir-0002/file-0001.bin"])
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
+    assert len([line for line in file_list if line.startswith("FC")]) == 2
+
+
+def test_file_track_target_generator(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track(f"dir-0001/file-000{i}.bin" for i in range(1, 3))
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
+    assert len([line for line in file_list if line.startswith("FC")]) == 2
+
+
+def test_file_track_manifest(xvc_repo_with_dir):
+    manifest = pathlib.Path(tempfile.mkdtemp()) / "targets.txt"
+    manifest.write_text("# data files\ndir-0001/file-0001.bin\n\ndir-0002/file-0001.bin\n")
+    xvc_repo_with_dir.file().track(Manifest(manifest), "dir-0001/file-0002.bin")
+    file_list = str(xvc_repo_with_dir.file().list()).split("\n")
+    assert len([line for line in file_list if line.startswith("FC")]) == 3
+
+
+def test_file_track_many_targets_commit_message(empty_xvc_repo):
+    os.mkdir("many")
+    paths = [f"many/file-{i:04}.txt" for i in range(25)]
+    for path in paths:
+        pathlib.Path(path).write_text(path)
+
+    empty_xvc_repo.file().track(path for path in paths)
+    file_list = str(empty_xvc_repo.file().list("many/")).split("\n")
+    assert len([line for line in file_list if line.startswith("FC")]) == 25
+
+    message = subprocess.run(
+        ["git", "log", "-1", "--format=%B"], capture_output=True, text=True, check=True
+    ).stdout
+    assert "many/file-0019.txt ... (5 more targets)" in message
+    assert "many/file-0020.txt" not in message
+
+
+def test_file_track_unsupported_type(xvc_repo_with_dir):
+    with pytest.raises(TypeError, match="targets"):
+        xvc_repo_with_dir.file().track({"dir-0001/file-0001.bin": True})
+
+
+def test_file_track_options(xvc_repo_with_dir):
+    options = TrackOptions(recheck_method=RecheckMethod.Symlink)
+    xvc_repo_with_dir.file().track("dir-0001/file-0001.bin", options=options)
+    assert os.path.islink("dir-0001/file-0001.bin")
+
+
+def test_file_track_options_with_kwargs(xvc_repo_with_dir):
+    options = TrackOptions(recheck_method=RecheckMethod.Symlink)
+    xvc_repo_with_dir.file().track("dir-0001/", options=options, no_parallel=True)
+    assert os.path.islink("dir-0001/file-0001.bin")
+
+    with pytest.raises(TypeError, match="recheck_method"):
+        xvc_repo_with_dir.file().track(
+            "dir-0002/", options=options, recheck_method="copy"
+        )
+
+
+def test_file_list_options(xvc_repo_with_dir):
+    options = ListOptions(format=ListFormat.Name, sort=ListSortCriteria.NameAsc)
+    file_list = xvc_repo_with_dir.file().list("dir-0001/", options=options)
+    names = [line for line in str(file_list).split("\n") if line.startswith("dir-0001")]
+    assert names == sorted(names)
+    assert len(names) == 3
+
+
+def test_file_list_recorded_timestamp(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/file-0001.bin")
+    file_list = xvc_repo_with_dir.file().list(
+        "dir-0001/file-0001.bin", format="{{rts}} {{name}}", no_summary=True
+    )
+    entry = file_list[0]
+    assert entry.recorded_mtime is not None
+    assert str(file_list) == f"{entry.recorded_mtime:>19} dir-0001/file-0001.bin"
+
+
+def test_file_list_entries(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/")
+    file_list = xvc_repo_with_dir.file().list()
+    tracked = [e for e in file_list if e.recheck_method == "copy"]
+    assert len(tracked) == 3
+    assert all(len(e.recorded_digest) == 64 for e in tracked)
+    assert not any(e.changed for e in file_list)
+    assert file_list.summary.total == len(file_list)
+    assert file_list.summary.cached_size == sum(e.recorded_size for e in tracked)
+
+    with open("dir-0001/file-0001.bin", "ab") as f:
+        f.write(b"changed")
+    file_list = xvc_repo_with_dir.file().list("dir-0001/")
+    changed = [e.path for e in file_list if e.changed]
+    assert changed == ["dir-0001/file-0001.bin"]
+
+
+def test_file_list_to_pandas(xvc_repo_with_dir):
+    pytest.importorskip("pandas")
+    xvc_repo_with_dir.file().track("dir-0001/")
+    df = xvc_repo_with_dir.file().list().to_pandas()
+    assert len(df) == 9
+    assert df["recheck_method"].notna().sum() == 3
+
+
+def test_file_open_at_ref(xvc_repo_with_dir):
+    path = "dir-0001/file-0001.bin"
+    xvc_repo_with_dir.file().track(path)
+    with open(path, "rb") as f:
+        first_version = f.read()
+
+    os.chmod(path, 0o644)
+    with open(path, "ab") as f:
+        f.write(b"second version")
+    xvc_repo_with_dir.file().carry_in(path)
+    with open(path, "rb") as f:
+        second_version = f.read()
+
+    with xvc_repo_with_dir.file().open(path, ref="HEAD~1") as f:
+        assert f.read() == first_version
+    with xvc_repo_with_dir.file().open(path) as f:
+        assert f.read() == second_version
+    with open(path, "rb") as f:
+        assert f.read() == second_version
+
+
+def test_file_open_errors(xvc_repo_with_dir):
+    with pytest.raises(FileNotFoundError, match="not tracked"):
+        xvc_repo_with_dir.file().open("dir-0001/file-0001.bin")
+
+    xvc_repo_with_dir.file().track("dir-0001/file-0001.bin")
+    with pytest.raises(ValueError, match="reading"):
+        xvc_repo_with_dir.file().open("dir-0001/file-0001.bin", mode="wb")
+    with pytest.raises(ValueError, match="unknown Git reference"):
+        xvc_repo_with_dir.file().open("dir-0001/file-0001.bin", ref="no-such-ref")
+
+
+def test_file_paths_relative_to_workdir(xvc_repo_with_dir, tmp_path):
+    repo = os.getcwd()
+    outside = tmp_path / "outside"
+    outside.mkdir()
+    os.chdir(outside)
+    xvc = Xvc(workdir=repo)
+
+    path = "dir-0001/put.bin"
+    xvc.file().put(path, b"written with put")
+    assert pathlib.Path(repo, path).read_bytes() == b"written with put"
+    assert not (outside / "dir-0001").exists()
+    with xvc.file().open(path) as f:
+        assert f.read() == b"written with put"
+    assert len(xvc.file().history(path)) == 1
+    xvc.file().annotate(path, split="train")
+    assert xvc.file().labels(path) == {"split": "train"}
+
+
+def test_file_send_bring_from_subdirectory(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    os.chdir("dir-0001")
+    xvc = Xvc()
+    xvc.file().track("file-0001.bin", "file-0002.bin")
+
+    summary = xvc.file().send("file-0001.bin", "file-0002.bin", storage="local")
+    assert summary.transferred == 2
+    content = pathlib.Path("file-0001.bin").read_bytes()
+    xvc.file().remove("file-0001.bin", "file-0002.bin", from_cache=True)
+    os.remove("file-0001.bin")
+
+    summary = xvc.file().bring("file-0001.bin", storage="local")
+    assert summary.transferred == 1
+    assert pathlib.Path("file-0001.bin").read_bytes() == content
+
+    with xvc.file().dataset("file-0002.bin", storage="local") as dataset:
+        assert dataset.paths == ["dir-0001/file-0002.bin"]
+        assert dataset[0].read() == pathlib.Path("file-0002.bin").read_bytes()
+
+
+def test_file_history_and_diff(xvc_repo_with_dir):
+    path = "dir-0001/file-0001.bin"
+    xvc_repo_with_dir.file().track(path)
+    first = xvc_repo_with_dir.file().hash(path)[path]
+
+    os.chmod(path, 0o644)
+    with open(path, "ab") as f:
+        f.write(b"second version")
+    xvc_repo_with_dir.file().carry_in(path)
+    second = xvc_repo_with_dir.file().hash(path)[path]
+
+    history = xvc_repo_with_dir.file().history(path)
+    assert [v.digest.hex for v in history] == [second.hex, first.hex]
+    assert history[0].size == os.path.getsize(path)
+    assert len(history[0].commit) == 40
+    assert "carry-in" in history[0].message
+
+    diff = xvc_repo_with_dir.file().diff(path, "HEAD~1", "HEAD")
+    assert diff.changed
+    assert diff.status == "modified"
+    assert diff.digest_a == first
+
+    assert not xvc_repo_with_dir.file().diff(path, "HEAD")
+
+
+def test_file_history_returning_version(xvc_repo_with_dir):
+    path = "dir-0001/file-0001.bin"
+    xvc_repo_with_dir.file().track(path)
+    with open(path, "rb") as f:
+        content = f.read()
+
+    for data in [content + b"second version", content]:
+        os.chmod(path, 0o644)
+        with open(path, "wb") as f:
+            f.write(data)
+        xvc_repo_with_dir.file().carry_in(path)
+
+    history = xvc_repo_with_dir.file().history(path)
+    assert len(history) == 3
+    assert history[0].digest == history[2].digest
+    assert history[0].digest != history[1].digest
+
+
+def test_file_history_without_commits(tmp_path, monkeypatch):
+    monkeypatch.chdir(tmp_path)
+    subprocess.run(["git", "init", "--quiet"], check=True)
+    xvc = Xvc(skip_git=True)
+    xvc.init()
+    pathlib.Path("data.txt").write_text("data")
+    xvc.file().track("data.txt")
+
+    assert xvc.file().history("data.txt") == []
+
+
+def test_file_status(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/file-0001.bin")
+    xvc_repo_with_dir.file().track("dir-0001/file-0002.bin")
+    xvc_repo_with_dir.file().track("dir-0001/file-0003.bin", recheck_method="symlink")
+    assert xvc_repo_with_dir.file().status("dir-0001/").clean
+
+    os.chmod("dir-0001/file-0001.bin", 0o644)
+    with open("dir-0001/file-0001.bin", "ab") as f:
+        f.write(b"modified")
+    os.remove("dir-0001/file-0002.bin")
+    content = pathlib.Path("dir-0001/file-0003.bin").read_bytes()
+    os.remove("dir-0001/file-0003.bin")
+    pathlib.Path("dir-0001/file-0003.bin").write_bytes(content)
+
+    status = xvc_repo_with_dir.file().status()
+    assert status.modified == ["dir-0001/file-0001.bin"]
+    assert status.missing == ["dir-0001/file-0002.bin"]
+    assert status.recheck_changed == ["dir-0001/file-0003.bin"]
+    assert len(status.untracked) == 6
+    assert not status.clean
+
+
+def test_file_status_storage(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().send("dir-0001/file-0001.bin", storage="local")
+
+    status = xvc_repo_with_dir.file().status("dir-0001/", storage="local")
+    assert status.not_in_storage == ["dir-0001/file-0002.bin", "dir-0001/file-0003.bin"]
+
+
+def test_file_put(xvc_repo_with_dir):
+    digest = xvc_repo_with_dir.file().put("artifacts/model.bin", b"model weights")
+    with open("artifacts/model.bin", "rb") as f:
+        assert f.read() == b"model weights"
+    assert xvc_repo_with_dir.file().hash_data(b"model weights") == digest
+    assert xvc_repo_with_dir.file().status("artifacts/").clean
+
+    with pytest.raises(FileExistsError):
+        xvc_repo_with_dir.file().put("artifacts/model.bin", b"new weights")
+
+    chunks = (bytes([i]) * 1024 for i in range(4))
+    digest = xvc_repo_with_dir.file().put(
+        "artifacts/model.bin", chunks, force=True, recheck_method="symlink"
+    )
+    assert os.path.islink("artifacts/model.bin")
+    assert os.path.getsize("artifacts/model.bin") == 4096
+    assert xvc_repo_with_dir.file().history("artifacts/model.bin")[0].digest == digest
+
+
+def test_file_put_array(xvc_repo_with_dir):
+    np = pytest.importorskip("numpy")
+    array = np.arange(100, dtype=np.int32)
+    xvc_repo_with_dir.file().put("array.bin", array)
+    assert np.fromfile("array.bin", dtype=np.int32).tolist() == array.tolist()
+
+
+def test_file_put_track_options(xvc_repo_with_dir):
+    options = TrackOptions(no_parallel=True, include_git_files=True)
+    digest = xvc_repo_with_dir.file().put("options.bin", b"options", options)
+    assert xvc_repo_with_dir.file().hash_data(b"options") == digest
+
+
+def test_file_put_policy(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().policy().add("models/*", RecheckMethod.Symlink)
+    xvc_repo_with_dir.file().put("models/model.bin", b"model")
+    assert os.path.islink("models/model.bin")
+    assert xvc_repo_with_dir.file().policy().deviations() == []
+
+
+def test_file_send_bring_progress(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    sizes = sum(
+        os.path.getsize(f"dir-0001/file-000{i}.bin") for i in range(1, 4)
+    )
+
+    events = []
+    summary = xvc_repo_with_dir.file().send(
+        "dir-0001/", storage="local", progress=events.append
+    )
+    assert summary.transferred == 3
+    assert summary.failed == 0
+    assert summary.bytes == sizes
+    assert [e.kind for e in events].count("finished") == 3
+
+    summary = xvc_repo_with_dir.file().send("dir-0001/", storage="local")
+    assert summary.skipped == 3
+    assert summary.transferred == 0
+
+    xvc_repo_with_dir.file().remove("dir-0001/file-0001.bin", from_cache=True)
+    os.remove("dir-0001/file-0001.bin")
+    summary = xvc_repo_with_dir.file().bring("dir-0001/", storage="local")
+    assert summary.transferred == 1
+    assert summary.skipped == 2
+    assert os.path.exists("dir-0001/file-0001.bin")
+
+
+def test_file_bring_filters(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().send("dir-0001/", storage="local")
+    for i in range(1, 4):
+        xvc_repo_with_dir.file().remove(f"dir-0001/file-000{i}.bin", from_cache=True)
+    os.remove("dir-0001/file-0003.bin")
+
+    plan = xvc_repo_with_dir.file().bring("dir-0001/", storage="local", dry_run=True)
+    assert plan.dry_run
+    assert plan.planned == 3
+    assert plan.transferred == 0
+    assert not os.path.exists("dir-0001/file-0003.bin")
+
+    plan = xvc_repo_with_dir.file().bring(
+        "dir-0001/", storage="local", dry_run=True, exclude=["*/file-0001.bin"]
+    )
+    assert plan.planned == 2
+    assert plan.excluded == 1
+
+    plan = xvc_repo_with_dir.file().bring(
+        "dir-0001/", storage="local", dry_run=True, max_size=0
+    )
+    assert plan.planned == 0
+
+    options = BringOptions(storage="local", dry_run=True, max_size="1G")
+    assert xvc_repo_with_dir.file().bring("dir-0001/", options=options).planned == 3
+
+    summary = xvc_repo_with_dir.file().bring(
+        "dir-0001/", storage="local", only_missing=True
+    )
+    assert summary.transferred == 1
+    assert summary.excluded == 2
+    assert os.path.exists("dir-0001/file-0003.bin")
+
+
+def test_file_dataset(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().send("dir-0001/", storage="local")
+    contents = {}
+    for i in range(1, 4):
+        path = f"dir-0001/file-000{i}.bin"
+        contents[path] = pathlib.Path(path).read_bytes()
+        xvc_repo_with_dir.file().remove(path, from_cache=True)
+
+    with xvc_repo_with_dir.file().dataset(
+        "dir-0001/*.bin", storage="local", prefetch=2, batch_size=2
+    ) as dataset:
+        assert len(dataset) == 3
+        assert sorted(dataset.paths) == sorted(contents)
+        for item in dataset:
+            assert item.read() == contents[item.path]
+        assert dataset[-1].path == dataset.paths[-1]
+
+
+def test_file_dataset_closed_when_collected(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().send("dir-0001/", storage="local")
+    xvc_repo_with_dir.file().remove("dir-0001/file-0001.bin", from_cache=True)
+
+    dataset = xvc_repo_with_dir.file().dataset("dir-0001/*.bin", storage="local")
+    local_path = dataset[0].local_path
+    del dataset
+    gc.collect()
+    assert os.path.exists(local_path)
+    assert xvc_repo_with_dir.file().status("dir-0001/").clean
+
+
+def test_file_dataset_without_storage(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().remove("dir-0001/file-0001.bin", from_cache=True)
+    dataset = xvc_repo_with_dir.file().dataset("dir-0001/")
+    with dataset[1].open() as f:
+        assert f.read() == pathlib.Path("dir-0001/file-0002.bin").read_bytes()
+    with pytest.raises(FileNotFoundError):
+        dataset[0]
+
+
+def test_file_share_invalid_duration(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    for duration in ["soon", "1h30", "", "1x"]:
+        with pytest.raises(ValueError):
+            xvc_repo_with_dir.file().share(
+                "dir-0001/", storage="local", duration=duration
+            )
+    # Valid durations reach the storage, which can't share local files
+    with pytest.raises(RuntimeError):
+        xvc_repo_with_dir.file().share("dir-0001/", storage="local", duration="1h30m")
+
+
+def test_file_errors_raised_in_quiet_mode(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    with pytest.raises(RuntimeError):
+        Xvc(quiet=True).file().share("dir-0001/", storage="local", duration="1h")
+
+
+@pytest.fixture
+def xvc_repo_with_minio(xvc_repo_with_dir):
+    """A repository with a MinIO storage named `minio`, set with the `XVC_TEST_MINIO_*`
+    variables and the credentials in `MINIO_ACCESS_KEY_ID` and `MINIO_SECRET_ACCESS_KEY`"""
+    endpoint = os.environ.get("XVC_TEST_MINIO_ENDPOINT")
+    bucket_name = os.environ.get("XVC_TEST_MINIO_BUCKET_NAME")
+    if not endpoint or not bucket_name:
+        pytest.skip("XVC_TEST_MINIO_ENDPOINT and XVC_TEST_MINIO_BUCKET_NAME are not set")
+    xvc_repo_with_dir.storage().new_minio(
+        name="minio",
+        endpoint=endpoint,
+        bucket_name=bucket_name,
+        region=os.environ.get("XVC_TEST_MINIO_REGION", "us-east-1"),
+        storage_prefix=f"xvc-py-test-{os.getpid()}",
+    )
+    return xvc_repo_with_dir
+
+
+def test_file_share(xvc_repo_with_minio, tmp_path):
+    xvc_repo_with_minio.file().track("dir-0001/")
+    xvc_repo_with_minio.file().send("dir-0001/", storage="minio")
+    digests = xvc_repo_with_minio.file().hash("dir-0001/")
+
+    before = datetime.datetime.now(datetime.timezone.utc)
+    urls = xvc_repo_with_minio.file().share(
+        "dir-0001/",
+        storage="minio",
+        duration="1h30m",
+        manifest=tmp_path / "urls.json",
+    )
+    after = datetime.datetime.now(datetime.timezone.utc)
+
+    assert sorted(u.path for u in urls) == sorted(digests.keys())
+    for url in urls:
+        assert url.digest == digests[url.path].hex
+        assert url.storage == "minio"
+        assert url.url.startswith("http")
+        assert str(url) == url.url
+        expires_at = datetime.datetime.fromisoformat(url.expires_at)
+        duration = datetime.timedelta(hours=1, minutes=30)
+        assert before + duration <= expires_at <= after + duration
+
+    rows = [u.to_dict() for u in urls]
+    assert json.loads((tmp_path / "urls.json").read_text()) == rows
+
+    xvc_repo_with_minio.file().share(
+        "dir-0001/", storage="minio", manifest=tmp_path / "urls.csv"
+    )
+    with open(tmp_path / "urls.csv", newline="") as f:
+        csv_rows = list(csv.DictReader(f))
+    assert [r["path"] for r in csv_rows] == [r["path"] for r in rows]
+    assert list(csv_rows[0].keys()) == ["path", "digest", "url", "storage", "expires_at"]
+
+
+def test_file_annotate(xvc_repo_with_dir):
+    xvc_repo_with_dir.file().track("dir-0001/")
+    labels = xvc_repo_with_dir.file().annotate(
+        "dir-0001/file-0001.bin", split="train", version=2
+    )
+    assert labels == {"split": "train", "version": "2"}
+    xvc_repo_with_dir.file().annotate("dir-0001/file-0002.bin", split="test")
+
+    train = xvc_repo_with_dir.file().list(labels={"split": "train"})
+    assert [e.path for e in train] == ["dir-0001/file-0001.bin"]
+    assert train[0].labels == {"split": "train", "version": "2"}
+    options = ListOptions(labels={"split": "test"})
+    test = xvc_repo_with_dir.file().list(options=options)
+    assert [e.path for e in test] == ["dir-0001/file-0002.bin"]
+
+    xvc_repo_with_dir.file().annotate("dir-0001/file-0001.bin", version=None)
+    labels = xvc_repo_with_dir.file().labels("dir-0001/file-0001.bin")
+    assert labels == {"split": "train"}
+
+    with pytest.raises(KeyError):
+        xvc_repo_with_dir.file().annotate("dir-0002/file-0001.bin", split="train")
+
+
+def test_file_duplicates(xvc_repo_with_dir):
+    shutil.copy("dir-0001/file-0001.bin", "dir-0001/copy.bin")
+    shutil.copy("dir-0001/file-0001.bin", "dir-0001/link.bin")
+    size = os.path.getsize("dir-0001/file-0001.bin")
+    xvc_repo_with_dir.file().track("dir-0001/file-0001.bin", "dir-0001/copy.bin")
+    xvc_repo_with_dir.file().track("dir-0001/link.bin", recheck_method="symlink")
+
+    report = xvc_repo_with_dir.file().duplicates("dir-0001/")
+    assert len(report) == 1
+    group = report[0]
+    assert sorted(group.paths) == [
+        "dir-0001/copy.bin",
+        "dir-0001/file-0001.bin",
+        "dir-0001/link.bin",
+    ]
+    assert group.size == size
+    assert group.saved_bytes == size
+    assert group.potential_savings == 2 * size
+    assert report.potential_savings == 2 * size
+
+
+def test_file_policy(xvc_repo_with_dir):
+    policy = xvc_repo_with_dir.file().policy()
+    policy.add("dir-0001/*", RecheckMethod.Symlink)
+    xvc_repo_with_dir.file().track("dir-0001/", "dir-0002/file-0001.bin")
+    assert os.path.islink("dir-0001/file-0001.bin")
+    assert not os.path.islink("dir-0002/file-0001.bin")
+    assert policy.deviations() == []
+
+    policy.add("dir-0002/*", "symlink")
+    assert [r.pattern for r in policy.rules()] == ["dir-0001/*", "dir-0002/*"]
+    deviations = policy.deviations()
+    assert [(d.path, d.recheck_method, d.expected) for d in deviations] == [
+        ("dir-0002/file-0001.bin", "copy", "symlink")
+    ]
+    policy.apply()
+    assert os.path.islink("dir-0002/file-0001.bin")
+
+    policy.remove("dir-0002/*")
+    assert len(policy) == 1
+    with pytest.raises(KeyError):
+        policy.remove("dir-0002/*")
+
+
+@pytest.fixture
+def http_server(tmp_path):
+    served = tmp_path / "served"
+    served.mkdir()
+    handler = functools.partial(
+        http.server.SimpleHTTPRequestHandler, directory=str(served)
+    )
+    server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), handler)
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    yield served, f"http://127.0.0.1:{server.server_address[1]}"
+    server.shutdown()
+
+
+def test_file_track_url(empty_xvc_repo, http_server):
+    served, base_url = http_server
+    remote = served / "data.csv"
+    remote.write_text("a,b\n1,2\n")
+    url = f"{base_url}/data.csv"
+
+    tracked = empty_xvc_repo.file().track_url(url, "data/data.csv")
+    assert tracked.path == "data/data.csv"
+    assert tracked.url == url
+    assert tracked.last_modified is not None
+    assert pathlib.Path("data/data.csv").read_text() == "a,b\n1,2\n"
+    assert "data/data.csv" in [e.path for e in empty_xvc_repo.file().list()]
+    with pytest.raises(FileExistsError):
+        empty_xvc_repo.file().track_url(url, "data/data.csv")
+    with pytest.raises(ValueError):
+        empty_xvc_repo.file().track_url("ftp://example.com/a", "a")
+    with pytest.raises(ValueError):
+        empty_xvc_repo.file().track_url(url, "invalid.csv", recheck_method="invalid")
+    assert not os.path.exists("invalid.csv")
+
+    [status] = empty_xvc_repo.file().urls()
+    assert status.changed is False
+    assert [r.changed for r in empty_xvc_repo.file().refresh_urls()] == [False]
+
+    remote.write_text("a,b\n1,2\n3,4\n")
+    mtime = remote.stat().st_mtime + 10
+    os.utime(remote, (mtime, mtime))
+    [status] = empty_xvc_repo.file().urls()
+    assert status.changed is True
+
+    [refreshed] = empty_xvc_repo.file().refresh_urls("data/")
+    assert refreshed.changed is True
+    assert refreshed.last_modified != tracked.last_modified
+    assert pathlib.Path("data/data.csv").read_text() == "a,b\n1,2\n3,4\n"
+    [status] = empty_xvc_repo.file().urls()
+    assert status.changed is False
+
+
+def test_file_verify(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    xvc_repo_with_dir.file().track("dir-0001/")
+    xvc_repo_with_dir.file().track("dir-0002/", recheck_method="hardlink")
+    xvc_repo_with_dir.file().send("dir-0002/", storage="local")
+
+    report = xvc_repo_with_dir.file().verify()
+    assert report.checked == 6
+    assert report.ok
+    assert len(report) == 0
+
+    with open("dir-0001/file-0001.bin", "ab") as f:
+        f.write(b"edited in place")
+    hardlink = "dir-0002/file-0001.bin"
+    original = pathlib.Path(hardlink).read_bytes()
+    os.chmod(hardlink, 0o644)
+    with open(hardlink, "r+b") as f:
+        f.write(b"corrupted")
+
+    report = xvc_repo_with_dir.file().verify()
+    assert [(i.path, i.kind) for i in report.issues] == [
+        ("dir-0001/file-0001.bin", "modified"),
+        ("dir-0002/file-0001.bin", "corrupted_cache"),
+    ]
+    assert not report.ok
+    shallow = xvc_repo_with_dir.file().verify("dir-0002/", deep=False)
+    assert len(shallow) == 0
+
+    report = xvc_repo_with_dir.file().verify("dir-0002/", repair=True)
+    assert report.issues[0].error == "no storage to bring the content from"
+    assert not report.ok
+
+    report = xvc_repo_with_dir.file().verify(repair=True, storage="local")
+    assert report.ok
+    assert pathlib.Path(hardlink).read_bytes() == original
+    assert len(xvc_repo_with_dir.file().verify()) == 0
+
+
+def test_file_verify_hash_error(xvc_repo_with_dir):
+    storage_dir = tempfile.mkdtemp()
+    xvc_repo_with_dir.storage().new_local(name="local", path=storage_dir)
+    path = "dir-0001/file-0001.bin"
+    xvc_repo_with_dir.file().track(path)
+    xvc_repo_with_dir.file().send(path, storage="local")
+
+    hex = xvc_repo_with_dir.file().hash(path)[path].hex
+    cache_file = pathlib.Path(".xvc", "b3", hex[:3], hex[3:6], hex[6:], "0.bin")
+    os.chmod(cache_file.parent, 0o755)
+    cache_file.unlink()
+    # A directory in place of the cache file can't be hashed
+    cache_file.mkdir()
+
+    report = xvc_repo_with_dir.file().verify(path, repair=True, storage="local")
+    # Reported as corrupted_cache if the directory could be hashed
+    assert [i.kind for i in report.issues] == ["hash_error"]
+    assert report.issues[0].error
+    assert report.issues[0].actual is None
+    assert not report.issues[0].repaired
+    assert not report.ok
+    assert cache_file.is_dir()
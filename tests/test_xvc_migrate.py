@@ -0,0 +1,130 @@
+import hashlib
+import os
+
+from xvc import Xvc
+
+
+def create_dvc_project():
+    content = b"line 1\nline 2\n"
+    md5 = hashlib.md5(content).hexdigest()
+    cache_dir = os.path.join(".dvc", "cache", "files", "md5", md5[:2])
+    os.makedirs(cache_dir)
+    with open(os.path.join(cache_dir, md5[2:]), "wb") as f:
+        f.write(content)
+    with open("data.txt.dvc", "w") as f:
+        f.write(
+            f"outs:\n- md5: {md5}\n  size: {len(content)}\n  hash: md5\n  path: data.txt\n"
+        )
+    with open("dvc.yaml", "w") as f:
+        f.write(
+            """
+stages:
+  count:
+    cmd: wc -l data.txt > count.txt
+    deps:
+    - data.txt
+    outs:
+    - count.txt
+  train:
+    foreach: [a, b]
+    do:
+      cmd: echo ${item}
+"""
+        )
+
+
+def test_migrate_from_dvc(empty_xvc_repo):
+    create_dvc_project()
+    report = empty_xvc_repo.migrate.from_dvc(".")
+
+    assert report.restored == ["data.txt"]
+    assert report.tracked == ["data.txt"]
+    assert report.steps == ["count"]
+    skipped = dict(report.skipped)
+    assert "train" in skipped
+    assert "count.txt" in skipped
+
+    with open("data.txt", "rb") as f:
+        assert f.read() == b"line 1\nline 2\n"
+    assert "data.txt" in [e.path for e in empty_xvc_repo.file().list()]
+    steps = empty_xvc_repo.pipeline().step().list(names_only=True)
+    assert steps.strip() == "count"
+
+
+def test_migrate_from_dvc_relative_to_workdir(empty_xvc_repo, tmp_path):
+    create_dvc_project()
+    repo = os.getcwd()
+    outside = tmp_path / "outside"
+    outside.mkdir()
+    os.chdir(outside)
+
+    report = Xvc(workdir=repo).migrate.from_dvc()
+
+    assert report.tracked == ["data.txt"]
+    assert report.steps == ["count"]
+    assert not (outside / "data.txt").exists()
+    with open(os.path.join(repo, "data.txt"), "rb") as f:
+        assert f.read() == b"line 1\nline 2\n"
+
+
+def write_lfs_pointer(path, content):
+    oid = hashlib.sha256(content).hexdigest()
+    with open(path, "w") as f:
+        f.write(
+            "version https://git-lfs.github.com/spec/v1\n"
+            f"oid sha256:{oid}\nsize {len(content)}\n"
+        )
+    return oid
+
+
+def test_migrate_from_git_lfs(empty_xvc_repo):
+    content = b"large content\n"
+    oid = write_lfs_pointer("data.bin", content)
+    object_dir = os.path.join(".git", "lfs", "objects", oid[:2], oid[2:4])
+    os.makedirs(object_dir)
+    with open(os.path.join(object_dir, oid), "wb") as f:
+        f.write(content)
+    write_lfs_pointer("missing.bin", b"not in the store\n")
+    with open(".gitattributes", "w") as f:
+        f.write("data.bin filter=lfs diff=lfs merge=lfs -text\n")
+        f.write("missing.bin filter=lfs diff=lfs merge=lfs -text\n")
+    os.system("git add data.bin missing.bin .gitattributes")
+    os.system("git commit -m 'Add LFS files'")
+
+    report = empty_xvc_repo.migrate.from_git_lfs()
+
+    assert [o.path for o in report.converted] == ["data.bin"]
+    assert report.converted[0].oid == oid
+    assert [o.path for o in report.missing] == ["missing.bin"]
+    assert report.removed_rules == [
+        ".gitattributes: data.bin filter=lfs diff=lfs merge=lfs -text"
+    ]
+    with open("data.bin", "rb") as f:
+        assert f.read() == content
+    with open(".gitattributes") as f:
+        assert f.read() == "missing.bin filter=lfs diff=lfs merge=lfs -text\n"
+    git_files = os.popen("git ls-files").read().split()
+    assert "data.bin" not in git_files
+    assert "missing.bin" in git_files
+    assert "data.bin" in [e.path for e in empty_xvc_repo.file().list()]
+
+
+def test_migrate_from_git_lfs_keeps_rules_of_other_pointers(empty_xvc_repo):
+    for path in ["data.bin", "other.bin"]:
+        content = f"content of {path}\n".encode()
+        oid = write_lfs_pointer(path, content)
+        object_dir = os.path.join(".git", "lfs", "objects", oid[:2], oid[2:4])
+        os.makedirs(object_dir, exist_ok=True)
+        with open(os.path.join(object_dir, oid), "wb") as f:
+            f.write(content)
+    with open(".gitattributes", "w") as f:
+        f.write("*.bin filter=lfs diff=lfs merge=lfs -text\n")
+    os.system("git add data.bin other.bin .gitattributes")
+    os.system("git commit -m 'Add LFS files'")
+
+    report = empty_xvc_repo.migrate.from_git_lfs("data.bin")
+
+    assert [o.path for o in report.converted] == ["data.bin"]
+    assert report.removed_rules == []
+    with open(".gitattributes") as f:
+        assert f.read() == "*.bin filter=lfs diff=lfs merge=lfs -text\n"
@@ -4,6 +4,8 @@ import yaml
 import sqlite3
 import time
 
+from xvc import StepDependencyOptions
+
 
 def test_pipeline_list(empty_xvc_repo):
     pipeline_table = empty_xvc_repo.pipeline().list()
@@ -403,3 +405,19 @@ INSERT INTO people VALUES ('Alice', 25, 'F'),
 # TODO: def test_pipeline_import(xvc_repo_with_dir):
 #     assert False
 #
+
+
+def test_pipeline_unknown_option(empty_xvc_repo):
+    with pytest.raises(TypeError, match="Did you mean 'pipeline_name'"):
+        empty_xvc_repo.pipeline().new(pipeline_nam="test")
+
+
+def test_pipeline_step_dependency_options(xvc_pipeline_single_step):
+    pipeline = xvc_pipeline_single_step.pipeline()
+    options = StepDependencyOptions(step_name="hello", glob=["dir-0001/*.bin"])
+    pipeline.step().dependency(options=options)
+    first_run = pipeline.run()
+    second_run = pipeline.run()
+
+    assert "hello xvc" in first_run
+    assert second_run.strip() == ""
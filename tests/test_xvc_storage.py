@@ -1,5 +1,6 @@
 import tempfile
 import os
+import pathlib
 
 
 # How to set virtual env in lazyvim python
@@ -31,6 +32,14 @@ def test_storage_remove(xvc_repo_with_dir):
     assert temp_dir not in str(xvc_repo_with_dir.storage().list())
 
 
+def test_storage_new_local_pathlike(xvc_repo_with_dir):
+    temp_dir = pathlib.Path(tempfile.mkdtemp())
+
+    xvc_repo_with_dir.storage().new_local(name="local", path=temp_dir)
+
+    assert str(temp_dir) in str(xvc_repo_with_dir.storage().list())
+
+
 #
 #
 #